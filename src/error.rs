@@ -17,6 +17,10 @@ pub enum Error {
     SemVer(#[from] ::semver::Error),
     #[error("{0}")]
     Msg(String),
+    #[error("the document changed while it was being linted")]
+    Stale,
+    #[error("the network timed out")]
+    Timeout,
 }
 
 impl From<&'static str> for Error {
@@ -0,0 +1,103 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The annotation that marks a line of a fixture the rule should alert on.
+pub(crate) const EXPECT: &str = "<!-- expect -->";
+
+/// A test document for a rule, found at `<Style>/tests/<Rule>.<ext>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Fixture {
+    /// The check under test (e.g., `Microsoft.Passive`).
+    pub check: String,
+    /// The YAML file that defines the check.
+    pub rule: PathBuf,
+    pub path: PathBuf,
+}
+
+/// `find` returns the fixtures of the style in `style`, skipping any that
+/// don't have a matching rule.
+pub(crate) fn find(style: &Path) -> Vec<Fixture> {
+    let name = match style.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return vec![],
+    };
+    let entries = match fs::read_dir(style.join("tests")) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut fixtures: Vec<Fixture> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?.to_string();
+            let rule = style.join(format!("{}.yml", stem));
+            rule.exists().then(|| Fixture {
+                check: format!("{}.{}", name, stem),
+                rule,
+                path,
+            })
+        })
+        .collect();
+    fixtures.sort_by(|a, b| a.path.cmp(&b.path));
+    fixtures
+}
+
+/// `expected` returns the (1-based) lines of `src` annotated with `EXPECT`.
+pub(crate) fn expected(src: &str) -> BTreeSet<usize> {
+    src.lines()
+        .enumerate()
+        .filter(|(_, l)| l.contains(EXPECT))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// `failures` describes where the lines a rule alerted on, `actual`, differ
+/// from the `expected` ones.
+pub(crate) fn failures(expected: &BTreeSet<usize>, actual: &BTreeSet<usize>) -> Vec<String> {
+    let missing = expected
+        .difference(actual)
+        .map(|l| format!("expected an alert on line {}", l));
+    let unexpected = actual
+        .difference(expected)
+        .map(|l| format!("unexpected alert on line {}", l));
+    missing.chain(unexpected).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures() {
+        let dir = tempfile::tempdir().unwrap();
+        let style = dir.path().join("Test");
+        fs::create_dir_all(style.join("tests")).unwrap();
+        fs::write(style.join("Terms.yml"), "extends: substitution\n").unwrap();
+        fs::write(style.join("tests/Terms.md"), "").unwrap();
+        fs::write(style.join("tests/Orphan.md"), "").unwrap();
+
+        let found = find(&style);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].check, "Test.Terms");
+        assert_eq!(found[0].rule, style.join("Terms.yml"));
+    }
+
+    #[test]
+    fn annotations() {
+        let src = "# Title\n\nWe utilize it. <!-- expect -->\n\nWe use it.\n";
+        let want = expected(src);
+        assert_eq!(want, BTreeSet::from([3]));
+
+        assert!(failures(&want, &BTreeSet::from([3])).is_empty());
+        assert_eq!(
+            failures(&want, &BTreeSet::from([5])),
+            vec![
+                "expected an alert on line 3".to_string(),
+                "unexpected alert on line 5".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use reqwest::header::RANGE;
+use reqwest::{NoProxy, Proxy, StatusCode};
+
+use crate::error::Error;
+
+/// The proxy set by the `proxy` option, which takes priority over the
+/// `HTTPS_PROXY`/`HTTP_PROXY` environment variables.
+static PROXY: RwLock<Option<String>> = RwLock::new(None);
+
+/// Whether the `offline` option is set, in which case we never touch the
+/// network.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// How long, in seconds, we wait to connect, and for each chunk of a
+/// download, before giving up (the `networkTimeout` option).
+static TIMEOUT: AtomicU64 = AtomicU64::new(30);
+
+/// How many times we retry a failed download (the `downloadRetries` option).
+static RETRIES: AtomicU32 = AtomicU32::new(3);
+
+/// `set_offline` enables or disables offline mode.
+pub(crate) fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// `is_offline` reports whether offline mode is enabled.
+pub(crate) fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// `online` fails fast, with an explanation, when offline mode is enabled.
+fn online() -> Result<(), Error> {
+    if is_offline() {
+        return Err(Error::from(
+            "Offline mode is enabled; disable the `offline` option to use this feature.",
+        ));
+    }
+    Ok(())
+}
+
+/// `set_timeout` sets how long, in seconds, we wait for the network.
+pub(crate) fn set_timeout(secs: u64) {
+    TIMEOUT.store(secs.max(1), Ordering::Relaxed);
+}
+
+fn timeout() -> Duration {
+    Duration::from_secs(TIMEOUT.load(Ordering::Relaxed))
+}
+
+/// `set_retries` sets how many times we retry a failed download.
+pub(crate) fn set_retries(retries: u32) {
+    RETRIES.store(retries, Ordering::Relaxed);
+}
+
+/// `set_proxy` routes all of our HTTP(S) requests through `url`; an empty
+/// string restores the default of using the environment's proxy, if any.
+pub(crate) fn set_proxy(url: &str) -> Result<(), Error> {
+    let proxy = match url {
+        "" => None,
+        url => {
+            // Make sure it's valid now rather than on the first request.
+            Proxy::all(url)?;
+            Some(url.to_string())
+        }
+    };
+    *PROXY.write().unwrap() = proxy;
+    Ok(())
+}
+
+/// `proxy` returns the explicitly configured proxy, honoring `NO_PROXY`.
+fn proxy() -> Result<Option<Proxy>, Error> {
+    match PROXY.read().unwrap().as_deref() {
+        Some(url) => Ok(Some(Proxy::all(url)?.no_proxy(NoProxy::from_env()))),
+        None => Ok(None),
+    }
+}
+
+/// `client` returns an async client for our requests.
+///
+/// Without an explicit proxy, `reqwest` uses `HTTPS_PROXY`, `HTTP_PROXY`, and
+/// `NO_PROXY` from the environment.
+pub(crate) fn client() -> Result<reqwest::Client, Error> {
+    online()?;
+    let mut builder = reqwest::Client::builder()
+        .user_agent("vale-ls")
+        .connect_timeout(timeout());
+    if let Some(proxy) = proxy()? {
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder.build()?)
+}
+
+/// `download` streams `url` into `file`.
+///
+/// Transient failures (timeouts, dropped connections, and server errors) are
+/// retried with exponential backoff, resuming from where the last attempt
+/// left off if the server supports it. The final error names the URL.
+pub(crate) async fn download(url: &str, file: &mut File) -> Result<(), Error> {
+    let retries = RETRIES.load(Ordering::Relaxed);
+
+    let mut attempt = 0;
+    loop {
+        match download_once(url, file).await {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt < retries && is_transient(&err) => {
+                tracing::warn!("Failed to download {} ({}); retrying.", url, err);
+                tokio::time::sleep(backoff(attempt)).await;
+                attempt += 1;
+            }
+            Err(err) => {
+                let err = match err {
+                    Error::Http(err) => Error::Http(err.without_url()),
+                    err => err,
+                };
+                return Err(Error::from(format!("Failed to download {}: {}", url, err)));
+            }
+        }
+    }
+}
+
+/// `download_once` makes a single attempt at downloading `url`, appending to
+/// whatever a previous attempt already wrote to `file`.
+async fn download_once(url: &str, file: &mut File) -> Result<(), Error> {
+    let written = file.stream_position()?;
+
+    let mut req = client()?.get(url);
+    if written > 0 {
+        req = req.header(RANGE, format!("bytes={}-", written));
+    }
+    let mut resp = req.send().await?.error_for_status()?;
+
+    if written > 0 && resp.status() != StatusCode::PARTIAL_CONTENT {
+        // The server ignored our range, so start over.
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+    }
+
+    loop {
+        let chunk = tokio::time::timeout(timeout(), resp.chunk())
+            .await
+            .map_err(|_| Error::Timeout)??;
+        match chunk {
+            Some(chunk) => file.write_all(&chunk)?,
+            None => return Ok(()),
+        }
+    }
+}
+
+/// `is_transient` reports whether a failed download is worth retrying.
+fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::Timeout => true,
+        Error::Http(err) => match err.status() {
+            Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+            None => err.is_timeout() || err.is_connect() || err.is_body() || err.is_request(),
+        },
+        _ => false,
+    }
+}
+
+/// `backoff` returns how long to wait before retry number `attempt` (counting
+/// from zero): one second, doubling each time, up to 30 seconds.
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt).min(30))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries() {
+        assert_eq!(backoff(0), Duration::from_secs(1));
+        assert_eq!(backoff(2), Duration::from_secs(4));
+        assert_eq!(backoff(10), Duration::from_secs(30));
+
+        assert!(is_transient(&Error::Timeout));
+        assert!(!is_transient(&Error::from("Offline mode is enabled.")));
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use globset::GlobBuilder;
 use regex::Regex;
 use tower_lsp::lsp_types::*;
 
@@ -9,7 +10,878 @@ use crate::pkg;
 use crate::styles::StylesPath;
 use crate::utils;
 
-pub fn key_to_info(key: &str) -> Option<&str> {
+/// The file names Vale looks for when searching for a config file.
+pub(crate) const CONFIG_NAMES: [&str; 3] = [".vale.ini", "_vale.ini", "vale.ini"];
+
+/// A section of a config file, spanning from its header to its last
+/// non-empty line.
+///
+/// The section before the first header (the "global" section) has an empty
+/// name.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Section {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// `find` searches `start` and its ancestors for a config file.
+pub(crate) fn find(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        for name in CONFIG_NAMES {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+/// `find_all` returns every config file under `root`, skipping anything
+/// `.gitignore`d.
+pub(crate) fn find_all(root: &Path) -> Vec<PathBuf> {
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .filter_entry(|e| e.file_name() != ".git")
+        .build();
+
+    let mut found: Vec<PathBuf> = walker
+        .flatten()
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .filter(|e| is_config(&e.path().to_string_lossy()))
+        .map(|e| e.into_path())
+        .collect();
+    found.sort();
+    found
+}
+
+/// `is_config` reports whether `path` names a config file.
+pub(crate) fn is_config(path: &str) -> bool {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or("");
+    CONFIG_NAMES.contains(&name)
+}
+
+/// `discover` finds the config file Vale would use for a document in `start`:
+/// the nearest one in its ancestors, then `$VALE_CONFIG_PATH`, then
+/// `$XDG_CONFIG_HOME/vale`, and finally `$HOME`.
+pub(crate) fn discover(start: &Path) -> Option<PathBuf> {
+    discover_with(start, |key| std::env::var(key).ok())
+}
+
+fn discover_with(start: &Path, var: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    if let Some(found) = find(start) {
+        return Some(found);
+    }
+
+    if let Some(path) = var("VALE_CONFIG_PATH").filter(|p| !p.is_empty()) {
+        let path = PathBuf::from(path);
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+
+    let home = var("HOME").filter(|p| !p.is_empty()).map(PathBuf::from);
+    let xdg = var("XDG_CONFIG_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|h| h.join(".config")));
+
+    [xdg.map(|x| x.join("vale")), home]
+        .into_iter()
+        .flatten()
+        .flat_map(|dir| CONFIG_NAMES.map(|name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// A `key = value` entry of a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Entry {
+    pub key: String,
+    /// The index of the entry's section in `Config::sections`.
+    pub section: usize,
+    /// The first and last lines of the entry, which differ if its value is
+    /// continued (with a trailing `\`) onto the following lines.
+    pub start: usize,
+    pub end: usize,
+    pub key_range: Range,
+    /// The value as written on the entry's first line.
+    pub value: String,
+    pub value_range: Range,
+    /// Each comma-separated part of the value, across all of its lines.
+    pub values: Vec<(Range, String)>,
+}
+
+/// What a line of a config file holds.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Line {
+    Blank,
+    Comment,
+    Header,
+    /// A line of the entry with the given index in `Config::entries`.
+    Entry(usize),
+    /// A line that's none of the above, such as a key without a value.
+    Invalid,
+}
+
+/// A parsed config file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Config {
+    pub sections: Vec<Section>,
+    pub entries: Vec<Entry>,
+    pub lines: Vec<Line>,
+}
+
+impl Config {
+    /// `parse` reads a config file, tolerating errors: lines it can't make
+    /// sense of are marked `Line::Invalid`.
+    pub(crate) fn parse(src: &str) -> Config {
+        let mut config = Config {
+            sections: vec![Section {
+                name: "".to_string(),
+                start: 0,
+                end: 0,
+            }],
+            entries: Vec::new(),
+            lines: Vec::new(),
+        };
+
+        let mut continued: Option<usize> = None;
+        for (i, line) in src.lines().enumerate() {
+            let trimmed = line.trim();
+            let kind = if let Some(index) = continued {
+                let entry = &mut config.entries[index];
+                entry.end = i;
+                entry.values.extend(split_values(i, 0, line));
+                Line::Entry(index)
+            } else if trimmed.is_empty() {
+                Line::Blank
+            } else if trimmed.starts_with('#') || trimmed.starts_with(';') {
+                Line::Comment
+            } else if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                config.sections.push(Section {
+                    name: trimmed[1..trimmed.len() - 1].to_string(),
+                    start: i,
+                    end: i,
+                });
+                Line::Header
+            } else if let Some((key, value)) = line.split_once('=') {
+                config
+                    .entries
+                    .push(parse_entry(i, key, value, config.sections.len() - 1));
+                Line::Entry(config.entries.len() - 1)
+            } else {
+                Line::Invalid
+            };
+
+            continued = match kind {
+                Line::Entry(index) if line.trim_end().ends_with('\\') => Some(index),
+                _ => None,
+            };
+            if kind != Line::Header && !trimmed.is_empty() {
+                config.sections.last_mut().unwrap().end = i;
+            }
+            config.lines.push(kind);
+        }
+
+        config
+    }
+
+    /// `entry_at` returns the entry on `line`, if any.
+    pub(crate) fn entry_at(&self, line: usize) -> Option<&Entry> {
+        match self.lines.get(line)? {
+            Line::Entry(index) => self.entries.get(*index),
+            _ => None,
+        }
+    }
+
+    /// `section_at` returns the index of the section `line` is in.
+    pub(crate) fn section_at(&self, line: usize) -> usize {
+        self.sections
+            .iter()
+            .rposition(|s| s.start <= line)
+            .unwrap_or(0)
+    }
+
+    /// `entries` returns every entry for `key`.
+    pub(crate) fn entries<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(move |e| e.key == key)
+    }
+
+    /// `section_name` returns the name of the section `entry` is in.
+    pub(crate) fn section_name(&self, entry: &Entry) -> &str {
+        self.sections
+            .get(entry.section)
+            .map_or("", |s| s.name.as_str())
+    }
+}
+
+/// `parse_entry` reads the `key = value` on `line`.
+fn parse_entry(line: usize, key: &str, value: &str, section: usize) -> Entry {
+    let span = |start: usize, len: usize| {
+        Range::new(
+            Position::new(line as u32, start as u32),
+            Position::new(line as u32, (start + len) as u32),
+        )
+    };
+
+    let indent = key.chars().take_while(|c| c.is_whitespace()).count();
+    let offset = key.chars().count() + 1;
+    let text = value.trim_end().trim_end_matches('\\').trim();
+    let lead = value.chars().take_while(|c| c.is_whitespace()).count();
+
+    Entry {
+        key: key.trim().to_string(),
+        section,
+        start: line,
+        end: line,
+        key_range: span(indent, key.trim().chars().count()),
+        value: text.to_string(),
+        value_range: span(offset + lead, text.chars().count()),
+        values: split_values(line, offset, value),
+    }
+}
+
+/// `split_values` returns the comma-separated parts of `text`, which starts at
+/// `offset` on `line`, along with their ranges.
+fn split_values(line: usize, offset: usize, text: &str) -> Vec<(Range, String)> {
+    let text = text.trim_end();
+    let text = text.strip_suffix('\\').unwrap_or(text);
+
+    let mut found = Vec::new();
+    let mut start = offset;
+    for value in text.split(',') {
+        let len = value.chars().count();
+        let lead = value.chars().take_while(|c| c.is_whitespace()).count();
+        let trimmed = value.trim();
+        if !trimmed.is_empty() {
+            let from = (start + lead) as u32;
+            let to = from + trimmed.chars().count() as u32;
+            found.push((
+                Range::new(
+                    Position::new(line as u32, from),
+                    Position::new(line as u32, to),
+                ),
+                trimmed.to_string(),
+            ));
+        }
+        start += len + 1;
+    }
+    found
+}
+
+/// `glob_matches` reports whether a section glob (e.g., `*.{md,txt}`) matches
+/// `path`, which is relative to the config file's directory.
+///
+/// As in Vale, a `*` also matches path separators, so `*.md` matches every
+/// Markdown file and `docs/**` every file under `docs`.
+pub(crate) fn glob_matches(glob: &str, path: &Path) -> bool {
+    match GlobBuilder::new(glob).literal_separator(false).build() {
+        Ok(glob) => glob.compile_matcher().is_match(path),
+        Err(_) => false,
+    }
+}
+
+/// `set_rule` returns the edit that sets `check` to `value` in the last
+/// section of `src` matching `path` (relative to the config file), creating
+/// a section for the path's extension if none exists.
+pub(crate) fn set_rule(src: &str, path: &Path, check: &str, value: &str) -> TextEdit {
+    let lines: Vec<&str> = src.lines().collect();
+    let config = Config::parse(src);
+    let setting = format!("{} = {}", check, value);
+
+    let section = config
+        .sections
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, s)| !s.name.is_empty() && glob_matches(&s.name, path));
+
+    if let Some((index, section)) = section {
+        if let Some(entry) = config.entries(check).find(|e| e.section == index) {
+            return TextEdit {
+                range: Range::new(
+                    Position::new(entry.start as u32, 0),
+                    Position::new(entry.end as u32, lines[entry.end].chars().count() as u32),
+                ),
+                new_text: setting,
+            };
+        }
+
+        let end = Position::new(
+            section.end as u32,
+            lines[section.end].chars().count() as u32,
+        );
+        return TextEdit {
+            range: Range::new(end, end),
+            new_text: format!("\n{}", setting),
+        };
+    }
+
+    let ext = path
+        .extension()
+        .map(|e| format!("*.{}", e.to_string_lossy()))
+        .unwrap_or("*".to_string());
+
+    let last = lines.len().saturating_sub(1);
+    let end = Position::new(
+        last as u32,
+        lines.last().map_or(0, |l| l.chars().count()) as u32,
+    );
+    TextEdit {
+        range: Range::new(end, end),
+        new_text: format!("\n\n[{}]\n{}\n", ext, setting),
+    }
+}
+
+/// `add_package` returns the edit that adds `name` to the `Packages` key of
+/// `src`, creating the key if needed, or `None` if it's already listed.
+pub(crate) fn add_package(src: &str, name: &str) -> Option<TextEdit> {
+    let lines: Vec<&str> = src.lines().collect();
+    let config = Config::parse(src);
+
+    if let Some(entry) = config.entries("Packages").find(|e| e.section == 0) {
+        if entry.values.iter().any(|(_, v)| v == name) {
+            return None;
+        }
+        return Some(append_value(&lines, entry, name));
+    }
+
+    // Keep the global keys together: after `StylesPath`, if it's set, or else
+    // at the top of the file.
+    let styles = config.entries("StylesPath").find(|e| e.section == 0);
+    let at = Position::new(styles.map_or(0, |e| e.end as u32 + 1), 0);
+    Some(TextEdit {
+        range: Range::new(at, at),
+        new_text: format!("Packages = {}\n", name),
+    })
+}
+
+/// `add_style` returns the edit that adds `name` to the `BasedOnStyles` of
+/// the `[*]` section, creating either if needed, or `None` if it's already
+/// listed.
+pub(crate) fn add_style(src: &str, name: &str) -> Option<TextEdit> {
+    let lines: Vec<&str> = src.lines().collect();
+    let config = Config::parse(src);
+    let section = config
+        .sections
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, s)| s.name == "*");
+
+    let (index, section) = match section {
+        Some(found) => found,
+        None => {
+            let last = lines.len().saturating_sub(1);
+            let end = Position::new(
+                last as u32,
+                lines.last().map_or(0, |l| l.chars().count()) as u32,
+            );
+            return Some(TextEdit {
+                range: Range::new(end, end),
+                new_text: format!("\n\n[*]\nBasedOnStyles = {}\n", name),
+            });
+        }
+    };
+
+    if let Some(entry) = config.entries("BasedOnStyles").find(|e| e.section == index) {
+        if entry.values.iter().any(|(_, v)| v == name) {
+            return None;
+        }
+        return Some(append_value(&lines, entry, name));
+    }
+
+    let at = Position::new(section.start as u32 + 1, 0);
+    Some(TextEdit {
+        range: Range::new(at, at),
+        new_text: format!("BasedOnStyles = {}\n", name),
+    })
+}
+
+/// `append_value` returns the edit that adds `value` to the end of `entry`'s
+/// comma-separated list.
+fn append_value(lines: &[&str], entry: &Entry, value: &str) -> TextEdit {
+    let end = Position::new(
+        entry.end as u32,
+        lines[entry.end].trim_end().chars().count() as u32,
+    );
+    let sep = if entry.values.is_empty() { " " } else { ", " };
+    TextEdit {
+        range: Range::new(end, end),
+        new_text: format!("{}{}", sep, value),
+    }
+}
+
+/// Keys that may only appear before the first section.
+const CORE_KEYS: [&str; 9] = [
+    "StylesPath",
+    "MinAlertLevel",
+    "IgnoredScopes",
+    "IgnoredClasses",
+    "SkippedScopes",
+    "WordTemplate",
+    "Vocab",
+    "Packages",
+    "NLPEndpoint",
+];
+
+/// Keys that may only appear inside a `[glob]` section.
+const FORMAT_KEYS: [&str; 6] = [
+    "BasedOnStyles",
+    "BlockIgnores",
+    "TokenIgnores",
+    "Transform",
+    "Lang",
+    "CommentDelimiters",
+];
+
+const LEVELS: [&str; 3] = ["suggestion", "warning", "error"];
+
+/// `diagnose` checks a config file for unknown keys, invalid values, and keys
+/// placed in the wrong section.
+pub(crate) fn diagnose(src: &str) -> Vec<Diagnostic> {
+    let rule_key = Regex::new(r"^[\w-]+\.[\w-]+$").unwrap();
+    let config = Config::parse(src);
+
+    let mut diagnostics = Vec::new();
+    for (i, (kind, line)) in config.lines.iter().zip(src.lines()).enumerate() {
+        let entry = match kind {
+            Line::Entry(index) if config.entries[*index].start == i => &config.entries[*index],
+            Line::Invalid => {
+                let indent = (line.chars().count() - line.trim_start().chars().count()) as u32;
+                diagnostics.push(config_diagnostic(
+                    Range::new(
+                        Position::new(i as u32, indent),
+                        Position::new(i as u32, indent + line.trim().chars().count() as u32),
+                    ),
+                    DiagnosticSeverity::ERROR,
+                    "Expected a `key = value` pair.".to_string(),
+                ));
+                continue;
+            }
+            _ => continue,
+        };
+
+        // Sections such as `[formats]` map arbitrary keys.
+        let section = config.section_name(entry);
+        if section == "formats" || section == "asciidoctor" {
+            continue;
+        }
+
+        let (key, value) = (entry.key.as_str(), entry.value.as_str());
+        if CORE_KEYS.contains(&key) {
+            if !section.is_empty() {
+                diagnostics.push(config_diagnostic(
+                    entry.key_range,
+                    DiagnosticSeverity::WARNING,
+                    format!("`{}` must be set before any section.", key),
+                ));
+            } else if key == "MinAlertLevel" && !LEVELS.contains(&value) {
+                diagnostics.push(config_diagnostic(
+                    entry.value_range,
+                    DiagnosticSeverity::ERROR,
+                    format!(
+                        "Invalid `MinAlertLevel` '{}'; expected one of {}.",
+                        value,
+                        LEVELS.join(", ")
+                    ),
+                ));
+            }
+        } else if FORMAT_KEYS.contains(&key) {
+            if section.is_empty() {
+                diagnostics.push(config_diagnostic(
+                    entry.key_range,
+                    DiagnosticSeverity::WARNING,
+                    format!("`{}` must be set inside a `[glob]` section.", key),
+                ));
+            }
+        } else if key.contains('.') {
+            if !rule_key.is_match(key) {
+                diagnostics.push(config_diagnostic(
+                    entry.key_range,
+                    DiagnosticSeverity::ERROR,
+                    format!("Malformed rule '{}'; expected `Style.Rule`.", key),
+                ));
+            } else if !["YES", "NO"].contains(&value) && !LEVELS.contains(&value) {
+                diagnostics.push(config_diagnostic(
+                    entry.value_range,
+                    DiagnosticSeverity::ERROR,
+                    format!(
+                        "Invalid value '{}'; expected YES, NO, or a severity level.",
+                        value
+                    ),
+                ));
+            }
+        } else {
+            let mut message = format!("Unknown key '{}'.", key);
+            if let Some(known) = closest_key(key) {
+                message = format!("Unknown key '{}'; did you mean `{}`?", key, known);
+            }
+            diagnostics.push(config_diagnostic(
+                entry.key_range,
+                DiagnosticSeverity::WARNING,
+                message,
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+/// `diagnose_rules` warns about `Style.Rule` overrides that don't match a rule
+/// in `styles`.
+pub(crate) fn diagnose_rules(src: &str, styles: &StylesPath) -> Vec<Diagnostic> {
+    let rule_key = Regex::new(r"^[\w-]+\.[\w-]+$").unwrap();
+    let config = Config::parse(src);
+
+    let mut diagnostics = Vec::new();
+    for entry in &config.entries {
+        let section = config.section_name(entry);
+        if section == "formats" || section == "asciidoctor" || !rule_key.is_match(&entry.key) {
+            continue;
+        }
+
+        let key = entry.key.as_str();
+        let (style, rule) = key.split_once('.').unwrap();
+        let message = match styles.rules(style) {
+            None => format!("Unknown style '{}'; is it installed?", style),
+            Some(rules) if !rules.iter().any(|r| r == rule) => {
+                let closest = rules
+                    .iter()
+                    .map(|r| (r, edit_distance(&r.to_lowercase(), &rule.to_lowercase())))
+                    .filter(|(_, d)| *d <= 3)
+                    .min_by_key(|(_, d)| *d);
+                match closest {
+                    Some((r, _)) => {
+                        format!("Unknown rule '{}'; did you mean `{}.{}`?", key, style, r)
+                    }
+                    None => format!("Unknown rule '{}'.", key),
+                }
+            }
+            _ => continue,
+        };
+
+        diagnostics.push(config_diagnostic(
+            entry.key_range,
+            DiagnosticSeverity::WARNING,
+            message,
+        ));
+    }
+
+    diagnostics
+}
+
+fn config_diagnostic(range: Range, severity: DiagnosticSeverity, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        source: Some("vale-ls".to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+/// `closest_key` suggests a known key within a small edit distance of `key`.
+fn closest_key(key: &str) -> Option<&'static str> {
+    CORE_KEYS
+        .iter()
+        .chain(FORMAT_KEYS.iter())
+        .map(|k| (*k, edit_distance(&k.to_lowercase(), &key.to_lowercase())))
+        .filter(|(_, d)| *d <= 3)
+        .min_by_key(|(_, d)| *d)
+        .map(|(k, _)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// `symbols` outlines a config file: the global keys followed by each section
+/// and its keys.
+#[allow(deprecated)]
+pub(crate) fn symbols(src: &str) -> Vec<DocumentSymbol> {
+    let lines: Vec<&str> = src.lines().collect();
+    let line_range = |i: usize| {
+        Range::new(
+            Position::new(i as u32, 0),
+            Position::new(i as u32, lines[i].chars().count() as u32),
+        )
+    };
+
+    let config = Config::parse(src);
+    let mut symbols = Vec::new();
+    for (index, section) in config.sections.iter().enumerate() {
+        let mut keys: Vec<DocumentSymbol> = config
+            .entries
+            .iter()
+            .filter(|e| e.section == index)
+            .map(|e| DocumentSymbol {
+                name: e.key.clone(),
+                detail: Some(e.value.clone()),
+                kind: SymbolKind::PROPERTY,
+                tags: None,
+                deprecated: None,
+                range: Range::new(line_range(e.start).start, line_range(e.end).end),
+                selection_range: e.key_range,
+                children: None,
+            })
+            .collect();
+
+        if section.name.is_empty() {
+            symbols.append(&mut keys);
+            continue;
+        }
+
+        symbols.push(DocumentSymbol {
+            name: format!("[{}]", section.name),
+            detail: None,
+            kind: SymbolKind::NAMESPACE,
+            tags: None,
+            deprecated: None,
+            range: Range::new(line_range(section.start).start, line_range(section.end).end),
+            selection_range: line_range(section.start),
+            children: Some(keys),
+        });
+    }
+
+    symbols
+}
+
+/// `key_values` returns each comma-separated value of every `key = a, b, c`
+/// entry for `key`, along with its range.
+pub(crate) fn key_values(src: &str, key: &str) -> Vec<(Range, String)> {
+    let config = Config::parse(src);
+    config.entries(key).flat_map(|e| e.values.clone()).collect()
+}
+
+/// `check_ranges` returns the range of the key of every `check = ...`
+/// override in `src`.
+pub(crate) fn check_ranges(src: &str, check: &str) -> Vec<Range> {
+    let config = Config::parse(src);
+    config.entries(check).map(|e| e.key_range).collect()
+}
+
+/// `rename_check` returns the edits that rename every `old = ...` override
+/// in `src` to `new`.
+pub(crate) fn rename_check(src: &str, old: &str, new: &str) -> Vec<TextEdit> {
+    check_ranges(src, old)
+        .into_iter()
+        .map(|range| TextEdit::new(range, new.to_string()))
+        .collect()
+}
+
+/// `key_at` returns the key under `position`, along with its range.
+pub(crate) fn key_at(src: &str, position: Position) -> Option<(String, Range)> {
+    let config = Config::parse(src);
+    let entry = config.entry_at(position.line as usize)?;
+    let range = entry.key_range;
+    if range.start.line != position.line
+        || position.character < range.start.character
+        || position.character > range.end.character
+    {
+        return None;
+    }
+    Some((entry.key.clone(), range))
+}
+
+/// `check_at` returns the key of a `Style.Rule = ...` override if `position`
+/// is within it.
+pub(crate) fn check_at(src: &str, position: Position) -> Option<String> {
+    let (key, _) = key_at(src, position)?;
+    let (style, rule) = key.split_once('.')?;
+    let valid = |s: &str| !s.is_empty() && !s.contains(char::is_whitespace);
+    if valid(style) && valid(rule) {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+/// `value_at` returns the key of a `key = a, b, c` entry along with the
+/// comma-separated value under `position`, if any.
+pub(crate) fn value_at(src: &str, position: Position) -> Option<(String, String)> {
+    let config = Config::parse(src);
+    let entry = config.entry_at(position.line as usize)?;
+    let (_, value) = entry.values.iter().find(|(range, _)| {
+        range.start.line == position.line
+            && range.start.character <= position.character
+            && position.character <= range.end.character
+    })?;
+    Some((entry.key.clone(), value.clone()))
+}
+
+/// `ignore_at` returns the key and the pattern under `position` of a
+/// `BlockIgnores` or `TokenIgnores` entry, including its continuation lines.
+pub(crate) fn ignore_at(src: &str, position: Position) -> Option<(String, String)> {
+    let (key, pattern) = value_at(src, position)?;
+    (key == "BlockIgnores" || key == "TokenIgnores").then_some((key, pattern))
+}
+
+/// `ignore_info` describes an ignore `pattern`: a breakdown of its parts and
+/// what the `key` it's listed under suppresses, with an optional link to
+/// open it on Regex101.
+pub(crate) fn ignore_info(key: &str, pattern: &str, link: Option<&str>) -> String {
+    let mut info = format!("**{}** pattern\n\n```regex\n{}\n```\n", key, pattern);
+
+    let parts = explain_pattern(pattern);
+    if !parts.is_empty() {
+        info.push('\n');
+        for (token, meaning) in parts {
+            info.push_str(&format!("- `{}`: {}\n", token, meaning));
+        }
+    }
+
+    info.push('\n');
+    info.push_str(match key {
+        "BlockIgnores" => {
+            "Vale skips each block this matches, such as a multi-line shortcode, \
+             as a whole. Patterns usually start with `(?s)` so that `.` matches \
+             newlines."
+        }
+        _ => {
+            "Vale skips each inline match, such as math or a macro, but still \
+             lints the rest of the paragraph."
+        }
+    });
+    if let Some(link) = link {
+        info.push_str(&format!("\n\n[Open on Regex101]({})", link));
+    }
+    info
+}
+
+/// `explain_pattern` breaks a regular expression into its parts, each with a
+/// short description. Runs of literal text (including escaped characters)
+/// are kept together.
+fn explain_pattern(pattern: &str) -> Vec<(String, String)> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut parts: Vec<(String, String)> = Vec::new();
+    let mut literal = String::new();
+
+    let flush = |literal: &mut String, parts: &mut Vec<(String, String)>| {
+        if !literal.is_empty() {
+            parts.push((literal.clone(), "the literal text".to_string()));
+            literal.clear();
+        }
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let rest: String = chars[i..].iter().collect();
+        let (len, meaning): (usize, String) = match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                let meaning = match chars[i + 1] {
+                    's' => "any whitespace character",
+                    'S' => "any non-whitespace character",
+                    'w' => "any word character",
+                    'W' => "any non-word character",
+                    'd' => "any digit",
+                    'D' => "any non-digit",
+                    'b' => "a word boundary",
+                    'B' => "not a word boundary",
+                    'n' => "a newline",
+                    't' => "a tab",
+                    'A' => "the start of the text",
+                    'z' => "the end of the text",
+                    _ => {
+                        literal.push('\\');
+                        literal.push(chars[i + 1]);
+                        i += 2;
+                        continue;
+                    }
+                };
+                (2, meaning.to_string())
+            }
+            '(' => {
+                let (len, meaning) = if let Some(flags) = rest
+                    .strip_prefix("(?")
+                    .and_then(|r| r.split_once(')'))
+                    .map(|(f, _)| f)
+                    .filter(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_alphabetic()))
+                {
+                    let names: Vec<&str> = flags
+                        .chars()
+                        .filter_map(|c| match c {
+                            'i' => Some("ignore case"),
+                            's' => Some("`.` matches newlines"),
+                            'm' => Some("`^` and `$` match at line breaks"),
+                            'U' => Some("quantifiers are lazy"),
+                            _ => None,
+                        })
+                        .collect();
+                    (flags.len() + 3, format!("flags: {}", names.join(", ")))
+                } else if rest.starts_with("(?:") {
+                    (3, "start of a non-capturing group".to_string())
+                } else if rest.starts_with("(?=") || rest.starts_with("(?!") {
+                    (3, "start of a lookahead".to_string())
+                } else if rest.starts_with("(?<=") || rest.starts_with("(?<!") {
+                    (4, "start of a lookbehind".to_string())
+                } else {
+                    (1, "start of a group".to_string())
+                };
+                (len, meaning)
+            }
+            ')' => (1, "end of the group".to_string()),
+            '[' => {
+                let mut end = i + 1;
+                if chars.get(end) == Some(&'^') {
+                    end += 1;
+                }
+                if chars.get(end) == Some(&']') {
+                    end += 1;
+                }
+                while end < chars.len() && chars[end] != ']' {
+                    end += if chars[end] == '\\' { 2 } else { 1 };
+                }
+                let len = (end + 1).min(chars.len()) - i;
+                let meaning = match chars.get(i + 1) {
+                    Some('^') => "any character not in the set",
+                    _ => "any character in the set",
+                };
+                (len, meaning.to_string())
+            }
+            '{' => match rest.find('}') {
+                Some(end) => (end + 1, "repeated the given number of times".to_string()),
+                None => (1, String::new()),
+            },
+            '*' => (1, "zero or more of the previous item".to_string()),
+            '+' => (1, "one or more of the previous item".to_string()),
+            '?' => (1, "makes the previous item optional (or lazy)".to_string()),
+            '.' => (1, "any character".to_string()),
+            '^' => (1, "the start of the text (or line)".to_string()),
+            '$' => (1, "the end of the text (or line)".to_string()),
+            '|' => (1, "or".to_string()),
+            c => {
+                literal.push(c);
+                i += 1;
+                continue;
+            }
+        };
+
+        if meaning.is_empty() {
+            literal.push(chars[i]);
+        } else {
+            flush(&mut literal, &mut parts);
+            let token: String = chars[i..(i + len).min(chars.len())].iter().collect();
+            parts.push((token, meaning));
+        }
+        i += len.max(1);
+    }
+    flush(&mut literal, &mut parts);
+    parts
+}
+
+pub fn key_to_info(key: &str) -> Option<&'static str> {
     match key {
         "StylesPath" => Some(include_str!("../doc/ini/StylesPath.md")),
         "MinAlertLevel" => Some(include_str!("../doc/ini/MinAlertLevel.md")),
@@ -27,75 +899,226 @@ pub fn key_to_info(key: &str) -> Option<&str> {
     }
 }
 
-pub async fn complete(line: &str, styles: PathBuf) -> Result<Vec<CompletionItem>, Error> {
-    let mut completions = Vec::new();
-    let re = Regex::new(r"\w+\.\w+ =").unwrap();
+/// `complete_section` suggests section headers for a line starting with `[`,
+/// including globs for the given workspace extensions.
+pub(crate) fn complete_section(
+    line_no: u32,
+    line: &str,
+    extensions: Vec<String>,
+) -> Vec<CompletionItem> {
+    let mut sections: Vec<(String, String)> = vec![
+        ("*".to_string(), "All files".to_string()),
+        (
+            "*.{md,mdx}".to_string(),
+            "Markdown and MDX files".to_string(),
+        ),
+        ("*.rst".to_string(), "reStructuredText files".to_string()),
+        ("*.adoc".to_string(), "AsciiDoc files".to_string()),
+        (
+            "formats".to_string(),
+            "Associate extensions with formats".to_string(),
+        ),
+    ];
+    for ext in extensions {
+        let glob = format!("*.{}", ext);
+        if !sections.iter().any(|(s, _)| *s == glob) {
+            sections.push((glob, "Found in this workspace".to_string()));
+        }
+    }
 
-    if line.contains("BasedOnStyles") {
-        completions = get_styles(line, styles)?;
-    } else if line.contains("MinAlertLevel") {
-        vec!["suggestion", "warning", "error"]
-            .into_iter()
-            .for_each(|s| {
-                completions.push(CompletionItem {
-                    label: s.to_string(),
-                    kind: Some(CompletionItemKind::VALUE),
-                    ..CompletionItem::default()
-                })
+    let content = line.trim_end_matches(['\r', '\n']);
+    let range = Range::new(
+        Position::new(line_no, 0),
+        Position::new(line_no, content.chars().count() as u32),
+    );
+
+    sections
+        .into_iter()
+        .map(|(name, description)| {
+            let header = format!("[{}]", name);
+            CompletionItem {
+                label: header.clone(),
+                kind: Some(CompletionItemKind::MODULE),
+                label_details: Some(CompletionItemLabelDetails {
+                    description: Some(description),
+                    ..CompletionItemLabelDetails::default()
+                }),
+                filter_text: Some(header.clone()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: header,
+                })),
+                ..CompletionItem::default()
+            }
+        })
+        .collect()
+}
+
+/// `complete` suggests values for the entry under `position`, or the rules
+/// of a `Style.` key that's being typed.
+pub async fn complete(
+    src: &str,
+    position: Position,
+    styles: PathBuf,
+) -> Result<Vec<CompletionItem>, Error> {
+    let config = Config::parse(src);
+    let line = position.line as usize;
+
+    let entry = match config.lines.get(line) {
+        Some(Line::Entry(index)) => &config.entries[*index],
+        Some(Line::Invalid) => {
+            let text = src.lines().nth(line).unwrap_or("");
+            return Ok(match partial_check(text) {
+                Some(style) => get_rules(style, styles),
+                None => complete_key(&config, line, ""),
             });
-    } else if line.contains("IgnoredScopes") {
-        completions = inline_tags();
-    } else if line.contains("SkippedScopes") {
-        completions = block_tags();
-    } else if re.is_match(line) {
-        completions = rule_options();
-    } else if line.contains("Vocab") {
-        completions = get_vocab(line, styles)?;
-    } else if line.contains("Packages") {
-        completions = get_pkgs(line).await?;
+        }
+        Some(Line::Blank) | None => return Ok(complete_key(&config, line, " = ")),
+        _ => return Ok(vec![]),
+    };
+    if entry.start == line && position.character <= entry.key_range.end.character {
+        return Ok(complete_key(&config, line, ""));
     }
 
+    let existing: Vec<&str> = entry.values.iter().map(|(_, v)| v.as_str()).collect();
+    let completions = match entry.key.as_str() {
+        "BasedOnStyles" => get_styles(&existing, styles)?,
+        "MinAlertLevel" => LEVELS
+            .into_iter()
+            .map(|s| CompletionItem {
+                label: s.to_string(),
+                kind: Some(CompletionItemKind::VALUE),
+                ..CompletionItem::default()
+            })
+            .collect(),
+        "IgnoredScopes" => inline_tags(),
+        "SkippedScopes" => block_tags(),
+        "Vocab" => get_vocab(&existing, styles)?,
+        "Packages" => get_pkgs(&existing).await?,
+        key if partial_check(key).is_some() => rule_options(),
+        _ => vec![],
+    };
+
     Ok(completions)
 }
 
-async fn get_pkgs(line: &str) -> Result<Vec<CompletionItem>, Error> {
+/// `complete_key` suggests the keys that may be set in the section `line` is
+/// in (the core keys at the top level and the format keys inside a `[glob]`)
+/// and aren't already.
+fn complete_key(config: &Config, line: usize, suffix: &str) -> Vec<CompletionItem> {
+    let section = config.section_at(line);
+    let keys: &[&str] = match config.sections[section].name.as_str() {
+        "" => &CORE_KEYS,
+        "formats" | "asciidoctor" => return vec![],
+        _ => &FORMAT_KEYS,
+    };
+
+    let set: Vec<&str> = config
+        .entries
+        .iter()
+        .filter(|e| e.section == section && !(e.start..=e.end).contains(&line))
+        .map(|e| e.key.as_str())
+        .collect();
+
+    keys.iter()
+        .filter(|key| !set.contains(key))
+        .map(|key| CompletionItem {
+            label: key.to_string(),
+            kind: Some(CompletionItemKind::PROPERTY),
+            insert_text: Some(format!("{}{}", key, suffix)),
+            documentation: key_to_info(key).map(|info| {
+                Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: info.to_string(),
+                })
+            }),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+async fn get_pkgs(existing: &[&str]) -> Result<Vec<CompletionItem>, Error> {
     let pkgs: Vec<pkg::Package> = pkg::fetch().await?;
 
     let completions = pkgs
         .into_iter()
-        .filter(|v| !line.contains(&v.name))
+        .filter(|v| !existing.contains(&v.name.as_str()))
         .map(|v| utils::pkg_to_completion(v))
         .collect();
 
     Ok(completions)
 }
 
-fn get_vocab(line: &str, styles: PathBuf) -> Result<Vec<CompletionItem>, Error> {
+fn get_vocab(existing: &[&str], styles: PathBuf) -> Result<Vec<CompletionItem>, Error> {
     let p = StylesPath::new(styles);
 
     let completions = p
         .get_vocab()?
         .into_iter()
-        .filter(|v| !line.contains(&v.name))
+        .filter(|v| !existing.contains(&v.name.as_str()))
         .map(|v| utils::entry_to_completion(v))
         .collect();
 
     Ok(completions)
 }
 
-fn get_styles(line: &str, styles: PathBuf) -> Result<Vec<CompletionItem>, Error> {
+fn get_styles(existing: &[&str], styles: PathBuf) -> Result<Vec<CompletionItem>, Error> {
     let p = StylesPath::new(styles);
 
     let completions = p
         .get_styles()?
         .into_iter()
-        .filter(|v| !line.contains(&v.name))
+        .filter(|v| !existing.contains(&v.name.as_str()))
         .map(|v| utils::entry_to_completion(v))
         .collect();
 
     Ok(completions)
 }
 
+/// `partial_check` returns the style of a `Style.` key that's being typed.
+fn partial_check(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.contains('=') {
+        return None;
+    }
+
+    let (style, rule) = line.split_once('.')?;
+    let word = |s: &str| {
+        s.chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    };
+    if !style.is_empty() && word(style) && word(rule) {
+        Some(style)
+    } else {
+        None
+    }
+}
+
+fn get_rules(style: &str, styles: PathBuf) -> Vec<CompletionItem> {
+    let p = StylesPath::new(styles);
+
+    p.rules(style)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|rule| {
+            let check = format!("{}.{}", style, rule);
+            CompletionItem {
+                label: rule.clone(),
+                insert_text: Some(rule),
+                kind: Some(CompletionItemKind::FIELD),
+                documentation: p.rule_path(&check).map(|path| {
+                    Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: path.display().to_string(),
+                    })
+                }),
+                detail: Some(check),
+                ..CompletionItem::default()
+            }
+        })
+        .collect()
+}
+
 fn rule_options() -> Vec<CompletionItem> {
     let mut completions = Vec::new();
 
@@ -143,3 +1166,337 @@ fn block_tags() -> Vec<CompletionItem> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_names() {
+        assert!(is_config("/docs/.vale.ini"));
+        assert!(is_config("/docs/_vale.ini"));
+        assert!(is_config("C:\\docs\\vale.ini"));
+        assert!(!is_config("/docs/my.vale.ini.bak"));
+        assert!(!is_config("/docs/setup.ini"));
+    }
+
+    #[test]
+    fn discovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let tmp = dir.path();
+        let xdg = tmp.join("xdg");
+        let home = tmp.join("home");
+        let doc = tmp.join("docs");
+        for dir in [xdg.join("vale"), home.clone(), doc.clone()] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+
+        let env = |xdg: &Path, home: &Path| {
+            let (xdg, home) = (xdg.display().to_string(), home.display().to_string());
+            move |key: &str| match key {
+                "XDG_CONFIG_HOME" => Some(xdg.clone()),
+                "HOME" => Some(home.clone()),
+                _ => None,
+            }
+        };
+
+        assert_eq!(discover_with(&doc, env(&xdg, &home)), None);
+
+        std::fs::write(home.join(".vale.ini"), "").unwrap();
+        assert_eq!(
+            discover_with(&doc, env(&xdg, &home)),
+            Some(home.join(".vale.ini"))
+        );
+
+        std::fs::write(xdg.join("vale").join(".vale.ini"), "").unwrap();
+        assert_eq!(
+            discover_with(&doc, env(&xdg, &home)),
+            Some(xdg.join("vale").join(".vale.ini"))
+        );
+
+        std::fs::write(doc.join("_vale.ini"), "").unwrap();
+        assert_eq!(
+            discover_with(&doc, env(&xdg, &home)),
+            Some(doc.join("_vale.ini"))
+        );
+
+        let mut all = vec![
+            home.join(".vale.ini"),
+            xdg.join("vale").join(".vale.ini"),
+            doc.join("_vale.ini"),
+        ];
+        all.sort();
+        assert_eq!(find_all(tmp), all);
+    }
+
+    const CONFIG: &str = "StylesPath = styles\n\n[*]\nBasedOnStyles = Vale\n\n[*.{md,txt}]\nVale.Spelling = NO\nVale.Terms = YES\n";
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches("*", Path::new("/docs/a.md")));
+        assert!(glob_matches("*.{md,txt}", Path::new("/docs/a.md")));
+        assert!(glob_matches("*.{md,txt}", Path::new("/docs/a.txt")));
+        assert!(!glob_matches("*.{md,txt}", Path::new("/docs/a.rst")));
+
+        assert!(glob_matches("*.{md,txt}", Path::new("docs/guide/a.md")));
+        assert!(glob_matches("docs/**", Path::new("docs/guide/a.md")));
+        assert!(!glob_matches("docs/**", Path::new("blog/a.md")));
+        assert!(glob_matches("*.[!r]st", Path::new("a.ast")));
+        assert!(!glob_matches("*.[!r]st", Path::new("a.rst")));
+        assert!(glob_matches("[ab].md", Path::new("b.md")));
+    }
+
+    #[test]
+    fn diagnostics() {
+        let src = "StylesPath = styles\nMinAlertLevel = info\nBasedOnStlyes = Vale\n\n[*]\nVocab = Base\nVale.Spelling = MAYBE\nVale. = NO\noops\n\n[formats]\nmdx = md\n";
+        let messages: Vec<(u32, String)> = diagnose(src)
+            .into_iter()
+            .map(|d| (d.range.start.line, d.message))
+            .collect();
+
+        assert_eq!(messages.len(), 6);
+        assert_eq!(messages[0].0, 1);
+        assert_eq!(
+            messages[1].1,
+            "Unknown key 'BasedOnStlyes'; did you mean `BasedOnStyles`?"
+        );
+        assert_eq!(messages[2].1, "`Vocab` must be set before any section.");
+        assert_eq!(messages[3].0, 6);
+        assert_eq!(messages[4].0, 7);
+        assert_eq!(messages[5].1, "Expected a `key = value` pair.");
+        assert!(diagnose(CONFIG).is_empty());
+    }
+
+    #[test]
+    fn unknown_rules() {
+        let styles = StylesPath::new(PathBuf::from(".github/styles"));
+        let src = "[*]\nTest.Rule = NO\nTest.Rul = NO\nTest.Other = NO\nVale.Spelling = NO\nGoogle.Passive = NO\n\n[formats]\nTest.x = md\n";
+        let messages: Vec<(u32, String)> = diagnose_rules(src, &styles)
+            .into_iter()
+            .map(|d| (d.range.start.line, d.message))
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    2,
+                    "Unknown rule 'Test.Rul'; did you mean `Test.Rule`?".to_string()
+                ),
+                (3, "Unknown rule 'Test.Other'.".to_string()),
+                (5, "Unknown style 'Google'; is it installed?".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rule_names() {
+        assert_eq!(partial_check("Test."), Some("Test"));
+        assert_eq!(partial_check("  Test.Ru"), Some("Test"));
+        assert_eq!(partial_check("Test.Rule = "), None);
+        assert_eq!(partial_check("StylesPath"), None);
+
+        let rules = get_rules("Test", PathBuf::from(".github/styles"));
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].label, "Rule");
+        assert_eq!(rules[0].detail.as_deref(), Some("Test.Rule"));
+        assert!(get_rules("Google", PathBuf::from(".github/styles")).is_empty());
+    }
+
+    #[test]
+    fn key_completion() {
+        let src = "StylesPath = styles\n\n[*]\nBasedOnStyles = Vale\nBlock\n\n[formats]\n";
+        let config = Config::parse(src);
+        let labels = |line: usize| -> Vec<String> {
+            complete_key(&config, line, " = ")
+                .into_iter()
+                .map(|c| c.label)
+                .collect()
+        };
+
+        let top = labels(1);
+        assert!(top.contains(&"MinAlertLevel".to_string()));
+        assert!(!top.contains(&"StylesPath".to_string()));
+        assert!(!top.contains(&"BasedOnStyles".to_string()));
+
+        let glob = labels(4);
+        assert!(glob.contains(&"BlockIgnores".to_string()));
+        assert!(!glob.contains(&"BasedOnStyles".to_string()));
+        assert!(!glob.contains(&"Packages".to_string()));
+        assert_eq!(labels(3).len(), FORMAT_KEYS.len());
+
+        assert!(labels(7).is_empty());
+    }
+
+    #[test]
+    fn outline() {
+        let symbols = symbols(CONFIG);
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["StylesPath", "[*]", "[*.{md,txt}]"]);
+
+        let children = symbols[2].children.as_ref().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[1].name, "Vale.Terms");
+        assert_eq!(children[1].detail.as_deref(), Some("YES"));
+    }
+
+    #[test]
+    fn values() {
+        let line = "BasedOnStyles = Vale, MyStyle";
+        assert_eq!(
+            value_at(line, Position::new(0, 17)),
+            Some(("BasedOnStyles".to_string(), "Vale".to_string()))
+        );
+        assert_eq!(
+            value_at(line, Position::new(0, 25)),
+            Some(("BasedOnStyles".to_string(), "MyStyle".to_string()))
+        );
+        assert_eq!(value_at(line, Position::new(0, 3)), None);
+    }
+
+    #[test]
+    fn value_ranges() {
+        let src = "StylesPath = styles\nPackages = Google,  write-good\n";
+        let found = key_values(src, "Packages");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].1, "Google");
+        assert_eq!(found[0].0.start, Position::new(1, 11));
+        assert_eq!(found[1].1, "write-good");
+        assert_eq!(
+            found[1].0,
+            Range::new(Position::new(1, 20), Position::new(1, 30))
+        );
+    }
+
+    #[test]
+    fn rename_checks() {
+        let src = "[*.md]\nVale.Terms = NO\n\n[*.txt]\n  Vale.Terms=YES\nVale.TermsX = NO\n";
+        let edits = rename_check(src, "Vale.Terms", "Vale.Words");
+        assert_eq!(edits.len(), 2);
+        assert_eq!(
+            edits[0].range,
+            Range::new(Position::new(1, 0), Position::new(1, 10))
+        );
+        assert_eq!(
+            edits[1].range,
+            Range::new(Position::new(4, 2), Position::new(4, 12))
+        );
+        assert_eq!(edits[1].new_text, "Vale.Words");
+
+        assert_eq!(
+            check_at("Vale.Terms = NO", Position::new(0, 3)),
+            Some("Vale.Terms".to_string())
+        );
+        assert_eq!(check_at("Vale.Terms = NO", Position::new(0, 13)), None);
+        assert_eq!(check_at("BasedOnStyles = Vale", Position::new(0, 3)), None);
+        assert_eq!(check_at("# Vale.Terms = NO", Position::new(0, 3)), None);
+    }
+
+    #[test]
+    fn packages() {
+        let edit = add_package(CONFIG, "Google").unwrap();
+        assert_eq!(edit.new_text, "Packages = Google\n");
+        assert_eq!(edit.range.start, Position::new(1, 0));
+
+        let src = "StylesPath = styles\nPackages = Google\n\n[*]\nBasedOnStyles = Vale\n";
+        let edit = add_package(src, "Microsoft").unwrap();
+        assert_eq!(edit.new_text, ", Microsoft");
+        assert_eq!(edit.range.start, Position::new(1, 17));
+        assert!(add_package(src, "Google").is_none());
+    }
+
+    #[test]
+    fn based_on_styles() {
+        let edit = add_style(CONFIG, "House").unwrap();
+        assert_eq!(edit.new_text, ", House");
+        assert_eq!(edit.range.start, Position::new(3, 20));
+        assert!(add_style(CONFIG, "Vale").is_none());
+
+        let edit = add_style("StylesPath = styles\n", "House").unwrap();
+        assert_eq!(edit.new_text, "\n\n[*]\nBasedOnStyles = House\n");
+    }
+
+    #[test]
+    fn parse_sections() {
+        let s = Config::parse(CONFIG).sections;
+        assert_eq!(s.len(), 3);
+        assert_eq!(s[1].name, "*");
+        assert_eq!((s[2].start, s[2].end), (5, 7));
+    }
+
+    #[test]
+    fn parse_config() {
+        let src =
+            "; comment = no\nStylesPath = styles\n\n[*]\nBasedOnStyles = Vale, \\\n  House\noops\n";
+        let config = Config::parse(src);
+        assert_eq!(
+            config.lines,
+            vec![
+                Line::Comment,
+                Line::Entry(0),
+                Line::Blank,
+                Line::Header,
+                Line::Entry(1),
+                Line::Entry(1),
+                Line::Invalid,
+            ]
+        );
+
+        let entry = config.entry_at(5).unwrap();
+        assert_eq!(entry.key, "BasedOnStyles");
+        assert_eq!(config.section_name(entry), "*");
+        assert_eq!((entry.start, entry.end), (4, 5));
+        assert_eq!(
+            entry.key_range,
+            Range::new(Position::new(4, 0), Position::new(4, 13))
+        );
+        let values: Vec<&str> = entry.values.iter().map(|(_, v)| v.as_str()).collect();
+        assert_eq!(values, vec!["Vale", "House"]);
+        assert_eq!(entry.values[1].0.start, Position::new(5, 2));
+
+        assert_eq!(config.entries("StylesPath").count(), 1);
+        assert!(config.entries("comment").next().is_none());
+    }
+
+    #[test]
+    fn disable_rule() {
+        let edit = set_rule(CONFIG, Path::new("a.md"), "Vale.Terms", "NO");
+        assert_eq!(edit.new_text, "Vale.Terms = NO");
+        assert_eq!(edit.range.start, Position::new(7, 0));
+
+        let edit = set_rule(CONFIG, Path::new("a.md"), "Vale.Avoid", "NO");
+        assert_eq!(edit.new_text, "\nVale.Avoid = NO");
+        assert_eq!(edit.range.start, Position::new(7, 16));
+
+        let edit = set_rule(CONFIG, Path::new("a.rs"), "Vale.Avoid", "NO");
+        assert_eq!(edit.range.start, Position::new(3, 20));
+    }
+
+    #[test]
+    fn ignore_patterns() {
+        let src = "[*.md]\nTokenIgnores = (\\$+[^\\n$]+\\$+), \\\n  \\{\\{<[^>]+>\\}\\}\nMinAlertLevel = warning\n";
+        assert_eq!(
+            ignore_at(src, Position::new(1, 20)),
+            Some(("TokenIgnores".to_string(), "(\\$+[^\\n$]+\\$+)".to_string()))
+        );
+        assert_eq!(
+            ignore_at(src, Position::new(2, 4)),
+            Some((
+                "TokenIgnores".to_string(),
+                "\\{\\{<[^>]+>\\}\\}".to_string()
+            ))
+        );
+        assert_eq!(ignore_at(src, Position::new(1, 3)), None);
+        assert_eq!(ignore_at(src, Position::new(3, 18)), None);
+
+        let parts = explain_pattern("(?s)\\bfoo\\.+[^a]{2}");
+        let tokens: Vec<&str> = parts.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(tokens, vec!["(?s)", "\\b", "foo\\.", "+", "[^a]", "{2}"]);
+        assert_eq!(parts[0].1, "flags: `.` matches newlines");
+        assert_eq!(parts[4].1, "any character not in the set");
+
+        let info = ignore_info("BlockIgnores", "(?s)x", Some("https://regex101.com/"));
+        assert!(info.starts_with("**BlockIgnores** pattern"));
+        assert!(info.ends_with("[Open on Regex101](https://regex101.com/)"));
+    }
+}
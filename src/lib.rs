@@ -12,11 +12,17 @@
 /// IDE-like features to any text editor that supports the Language Server
 /// Protocol (LSP).
 pub mod error;
+pub mod fixture;
+pub mod http;
 pub mod ini;
+pub mod logging;
 pub mod pkg;
 pub mod regex101;
+pub mod report;
 pub mod server;
+pub mod settings;
 pub mod styles;
 pub mod utils;
 pub mod vale;
+pub mod vocab;
 pub mod yml;
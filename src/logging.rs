@@ -0,0 +1,117 @@
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+
+use crate::error::Error;
+
+/// How many days of rotated logs to keep.
+const MAX_LOG_FILES: usize = 7;
+
+static FILE: OnceLock<(reload::Handle<LevelFilter, Registry>, LogFile)> = OnceLock::new();
+
+/// `LogFile` is where the file layer writes, which is swapped out whenever the
+/// `logFile` setting changes.
+///
+/// NOTE: We reload the file layer's level and writer rather than the layer
+/// itself since `tracing-subscriber` doesn't support reloading filtered
+/// layers.
+#[derive(Clone, Default)]
+struct LogFile(Arc<Mutex<Option<RollingFileAppender>>>);
+
+impl io::Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.0.lock().unwrap().as_mut() {
+            Some(file) => file.write(buf),
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.0.lock().unwrap().as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `init` sets up logging to stderr, filtered by `RUST_LOG` (only errors by
+/// default), and to the file set by `set_file`, once there is one.
+///
+/// NOTE: Logs must never go to stdout, which the server uses to talk to the
+/// client.
+pub fn init() {
+    let writer = LogFile::default();
+    let (level, handle) = reload::Layer::new(LevelFilter::OFF);
+    let file = {
+        let writer = writer.clone();
+        fmt::layer()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_filter(level)
+    };
+    let stderr = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(EnvFilter::from_default_env());
+
+    if tracing_subscriber::registry()
+        .with(file)
+        .with(stderr)
+        .try_init()
+        .is_ok()
+    {
+        let _ = FILE.set((handle, writer));
+    }
+}
+
+/// `set_file` writes the events at `level` (`info` by default) and above to
+/// `path`, which is rotated daily (e.g., `vale-ls.log.2024-01-31`). An empty
+/// `path` stops logging to a file.
+pub(crate) fn set_file(path: &str, level: &str) -> Result<(), Error> {
+    let (handle, writer) = match FILE.get() {
+        Some(file) => file,
+        None => return Ok(()),
+    };
+
+    let level = match level {
+        "" => LevelFilter::INFO,
+        level => level
+            .parse::<LevelFilter>()
+            .map_err(|_| Error::from(format!("unknown level '{}'", level)))?,
+    };
+
+    let appender = match path {
+        "" => None,
+        path => {
+            let path = Path::new(path);
+            let name = path
+                .file_name()
+                .ok_or_else(|| Error::from("expected a file name"))?;
+            let dir = path
+                .parent()
+                .filter(|d| !d.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+
+            let appender = RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(name.to_string_lossy())
+                .max_log_files(MAX_LOG_FILES)
+                .build(dir)
+                .map_err(|e| Error::from(e.to_string()))?;
+            Some(appender)
+        }
+    };
+
+    let level = if appender.is_some() {
+        level
+    } else {
+        LevelFilter::OFF
+    };
+    *writer.0.lock().unwrap() = appender;
+    handle.reload(level).map_err(|e| Error::from(e.to_string()))
+}
@@ -1,29 +1,150 @@
-use clap::Parser;
-use dashmap::DashMap;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use clap::{Parser, Subcommand};
+use dashmap::{DashMap, DashSet};
 use tower_lsp::{LspService, Server};
 
-use vale_ls::server::Backend;
+use vale_ls::logging;
+use vale_ls::report;
+use vale_ls::server::{Backend, ValeStatus};
+use vale_ls::utils::Ignores;
 use vale_ls::vale::ValeManager;
 
 /// The official Vale Language Server.
 #[derive(Parser, Debug)]
 #[command(version)]
-struct Args;
+struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Download and install the managed Vale binary, then exit.
+    Install {
+        /// The version to install (defaults to the latest release).
+        #[arg(long)]
+        version: Option<String>,
+
+        /// The directory to install into (defaults to the one `vale-ls` uses).
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// The release asset's platform and architecture, such as
+        /// `Linux_arm64` (defaults to this machine's).
+        #[arg(long)]
+        arch: Option<String>,
+    },
+    /// Lint a file or directory without starting the server.
+    Lint {
+        /// The file or directory to lint.
+        path: PathBuf,
+
+        /// The output format.
+        #[arg(long, default_value = "sarif", value_parser = ["sarif", "json"])]
+        format: String,
+
+        /// The config file to use (defaults to the one Vale would find).
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// A Vale filter expression, as in `vale --filter`.
+        #[arg(long, default_value = "")]
+        filter: String,
+    },
+}
+
+/// `install` runs the `install` subcommand.
+async fn install(version: Option<String>, dir: Option<PathBuf>, arch: Option<String>) {
+    let cli = ValeManager::new();
+    cli.set_arch(arch.as_deref().unwrap_or(""));
+    let result = cli.install_to(version.as_deref(), dir.as_deref()).await;
+
+    match result {
+        Ok(status) => println!("{}", status),
+        Err(err) => {
+            eprintln!("Failed to install Vale: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// `lint` runs the `lint` subcommand, exiting with 1 if there are errors
+/// (as Vale does) and 2 if linting failed.
+fn lint(path: PathBuf, format: String, config: Option<PathBuf>, filter: String) {
+    let files = match report::lint(&ValeManager::new(), &path, config, &filter) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("Failed to lint {}: {}", path.display(), err);
+            process::exit(2);
+        }
+    };
+
+    let output = match format.as_str() {
+        "json" => report::to_json(&files),
+        _ => report::to_sarif(&files, &env::current_dir().unwrap_or_default()),
+    };
+    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+
+    if report::has_errors(&files) {
+        process::exit(1);
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    env_logger::init();
+    logging::init();
+
+    let args = Args::parse();
+    match args.command {
+        Some(Commands::Install { version, dir, arch }) => {
+            install(version, dir, arch).await;
+            return;
+        }
+        Some(Commands::Lint {
+            path,
+            format,
+            config,
+            filter,
+        }) => {
+            lint(path, format, config, filter);
+            return;
+        }
+        None => {}
+    }
 
-    let _ = Args::parse();
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
     let (service, socket) = LspService::build(|client| Backend {
         client,
-        document_map: DashMap::new(),
-        param_map: DashMap::new(),
+        document_map: Arc::new(DashMap::new()),
+        param_map: Arc::new(DashMap::new()),
+        client_caps: Arc::new(OnceLock::new()),
+        debounce_map: Arc::new(DashMap::new()),
+        generation_map: Arc::new(DashMap::new()),
+        language_map: Arc::new(DashMap::new()),
+        diagnostic_map: Arc::new(DashMap::new()),
+        published_map: Arc::new(DashMap::new()),
+        linted_map: Arc::new(DashMap::new()),
+        lint_cache: Arc::new(DashMap::new()),
+        large_files: Arc::new(DashSet::new()),
+        lint_slots: Arc::new(RwLock::new(None)),
+        version_map: Arc::new(DashMap::new()),
+        fixture_map: Arc::new(DashMap::new()),
+        styles_map: Arc::new(DashMap::new()),
+        ignores: Arc::new(Ignores::default()),
+        status: Arc::new(RwLock::new(ValeStatus::default())),
         cli: ValeManager::new(),
     })
+    .custom_method("textDocument/diagnostic", Backend::diagnostic)
+    .custom_method("workspace/diagnostic", Backend::workspace_diagnostic)
+    .custom_method("vale/stats", Backend::stats)
+    .custom_method("vale/stylesPath", Backend::styles_path)
+    .custom_method("vale/testRule", Backend::test_rule)
     .finish();
 
     Server::new(stdin, stdout, socket).serve(service).await;
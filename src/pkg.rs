@@ -1,19 +1,117 @@
-use reqwest;
-use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
+use crate::http;
 
 const PKGS: &str = "https://raw.githubusercontent.com/errata-ai/packages/master/library.json";
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Package {
     pub name: String,
     pub description: String,
     pub homepage: String,
 }
 
+/// How long, in seconds, a cached copy of the library is used before we
+/// fetch it again.
+static TTL: AtomicU64 = AtomicU64::new(24 * 60 * 60);
+
+/// The library as of the last time we read or fetched it.
+static LIBRARY: RwLock<Option<(SystemTime, Vec<Package>)>> = RwLock::new(None);
+
+/// `set_ttl` sets how long, in seconds, the cached library stays fresh.
+pub(crate) fn set_ttl(secs: u64) {
+    TTL.store(secs, Ordering::Relaxed);
+}
+
+/// `fetch` returns the package library.
+///
+/// The library is cached in memory and on disk for the configured TTL. If it
+/// can't be fetched (e.g., in offline mode), a stale copy is used instead.
 pub async fn fetch() -> Result<Vec<Package>, Error> {
-    let resp = reqwest::get(PKGS).await?;
+    let ttl = Duration::from_secs(TTL.load(Ordering::Relaxed));
+
+    let cached = LIBRARY.read().unwrap().clone();
+    let cached = cached.or_else(|| cache_path().and_then(|p| read_cache(&p)));
+    if let Some((fetched, pkgs)) = &cached {
+        if is_fresh(*fetched, ttl) {
+            *LIBRARY.write().unwrap() = cached.clone();
+            return Ok(pkgs.clone());
+        }
+    }
+
+    match download().await {
+        Ok(pkgs) => {
+            if let Some(path) = cache_path() {
+                // NOTE: The cache is an optimization, so we ignore errors.
+                let _ = write_cache(&path, &pkgs);
+            }
+            *LIBRARY.write().unwrap() = Some((SystemTime::now(), pkgs.clone()));
+            Ok(pkgs)
+        }
+        Err(err) => match cached {
+            Some((_, pkgs)) => Ok(pkgs),
+            None => Err(err),
+        },
+    }
+}
+
+async fn download() -> Result<Vec<Package>, Error> {
+    let resp = http::client()?.get(PKGS).send().await?;
     let info: Vec<Package> = resp.json().await?;
     Ok(info)
 }
+
+fn is_fresh(fetched: SystemTime, ttl: Duration) -> bool {
+    fetched.elapsed().is_ok_and(|age| age < ttl)
+}
+
+/// `cache_path` returns where the library is cached on disk: a `vale-ls`
+/// directory in the platform's cache directory (e.g., `~/.cache` on Linux or
+/// `~/Library/Caches` on macOS).
+fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("vale-ls").join("library.json"))
+}
+
+/// `read_cache` loads a cached library along with when it was fetched.
+fn read_cache(path: &Path) -> Option<(SystemTime, Vec<Package>)> {
+    let fetched = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let pkgs = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+    Some((fetched, pkgs))
+}
+
+fn write_cache(path: &Path, pkgs: &[Package]) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_vec(pkgs)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vale-ls").join("library.json");
+        let pkgs = vec![Package {
+            name: "Google".to_string(),
+            description: "Google's style guide.".to_string(),
+            homepage: "https://github.com/errata-ai/Google".to_string(),
+        }];
+
+        write_cache(&path, &pkgs).unwrap();
+        let (fetched, cached) = read_cache(&path).unwrap();
+        assert_eq!(cached[0].name, "Google");
+        assert!(is_fresh(fetched, Duration::from_secs(60)));
+        assert!(!is_fresh(fetched, Duration::ZERO));
+    }
+}
@@ -1,9 +1,11 @@
 use std::collections::HashMap;
+use std::sync::RwLock;
 
-use reqwest;
 use serde::Deserialize;
+use tower_lsp::lsp_types::Url;
 
 use crate::error::Error;
+use crate::http;
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -16,21 +18,74 @@ pub(crate) struct Regex101Session {
     pub is_library_entry: bool,
 }
 
-pub(crate) fn upload(pattern: String) -> Result<Regex101Session, Error> {
+/// The flavors Regex101 supports.
+const FLAVORS: [&str; 8] = [
+    "pcre",
+    "pcre2",
+    "ecmascript",
+    "python",
+    "golang",
+    "java",
+    "dotnet",
+    "rust",
+];
+
+/// The flavor set by the `regex101Flavor` option, if any.
+static FLAVOR: RwLock<Option<String>> = RwLock::new(None);
+
+/// `set_flavor` sets the regex flavor we test patterns against; an empty string
+/// restores the default of `golang`, the closest match to Vale's engine.
+pub(crate) fn set_flavor(flavor: &str) -> Result<(), Error> {
+    let flavor = match flavor {
+        "" => None,
+        f if FLAVORS.contains(&f) => Some(f.to_string()),
+        f => {
+            return Err(Error::from(format!(
+                "'{}' isn't one of {}.",
+                f,
+                FLAVORS.join(", ")
+            )))
+        }
+    };
+    *FLAVOR.write().unwrap() = flavor;
+    Ok(())
+}
+
+fn flavor() -> String {
+    FLAVOR
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or("golang".to_string())
+}
+
+/// `link` returns a Regex101 URL that opens `pattern` without uploading it.
+pub(crate) fn link(pattern: &str) -> Result<String, Error> {
+    let url = Url::parse_with_params(
+        "https://regex101.com/",
+        &[("regex", pattern), ("flavor", &flavor()), ("flags", "gm")],
+    )
+    .map_err(|e| Error::from(e.to_string()))?;
+    Ok(url.to_string())
+}
+
+pub(crate) async fn upload(pattern: String) -> Result<Regex101Session, Error> {
+    let flavor = flavor();
     let mut map = HashMap::new();
 
     map.insert("regex", pattern.as_str());
     map.insert("flags", "gm");
     map.insert("testString", "Enter your test content here.");
-    map.insert("flavor", "pcre2");
+    map.insert("flavor", flavor.as_str());
     map.insert("delimiter", "/");
 
-    let resp = reqwest::blocking::Client::new()
+    let resp = http::client()?
         .post("https://regex101.com/api/regex")
         .json(&map)
-        .send()?;
+        .send()
+        .await?;
 
-    let body = resp.text()?;
+    let body = resp.text().await?;
     let session: Regex101Session = serde_json::from_str(&body)?;
 
     Ok(session)
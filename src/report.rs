@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ropey::Rope;
+use serde_json::{json, Value};
+use tower_lsp::lsp_types::*;
+
+use crate::error::Error;
+use crate::ini;
+use crate::utils;
+use crate::vale::ValeManager;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// `lint` runs Vale on a file or directory outside of the server, returning
+/// the diagnostics the server would publish for each file with alerts.
+///
+/// Columns are counted in Unicode code points, as SARIF expects.
+pub fn lint(
+    cli: &ValeManager,
+    path: &Path,
+    config: Option<PathBuf>,
+    filter: &str,
+) -> Result<BTreeMap<PathBuf, Vec<Diagnostic>>, Error> {
+    let path = path.canonicalize()?;
+    let start = if path.is_dir() {
+        path.as_path()
+    } else {
+        path.parent().unwrap_or(&path)
+    };
+    let config = config
+        .or_else(|| ini::discover(start))
+        .map(|c| c.display().to_string())
+        .unwrap_or_default();
+
+    let results = if path.is_dir() {
+        cli.run_dir(path.clone(), config, filter.to_string())?
+    } else {
+        cli.run(path.clone(), config, filter.to_string())?
+    };
+
+    let mut files = BTreeMap::new();
+    for (file, alerts) in results {
+        let file = PathBuf::from(file);
+        let rope = fs::File::open(&file)
+            .ok()
+            .and_then(|f| Rope::from_reader(f).ok());
+        let diagnostics = alerts
+            .iter()
+            .map(|alert| {
+                utils::alert_to_diagnostic(alert, rope.as_ref(), &PositionEncodingKind::UTF32, None)
+            })
+            .collect();
+        files.insert(file, diagnostics);
+    }
+
+    Ok(files)
+}
+
+/// `has_errors` reports whether any of the diagnostics is an error.
+pub fn has_errors(files: &BTreeMap<PathBuf, Vec<Diagnostic>>) -> bool {
+    files
+        .values()
+        .flatten()
+        .any(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+}
+
+/// `to_json` renders the diagnostics as a map of file paths to LSP
+/// diagnostics.
+pub fn to_json(files: &BTreeMap<PathBuf, Vec<Diagnostic>>) -> Value {
+    json!(files
+        .iter()
+        .map(|(file, diagnostics)| (file.display().to_string(), diagnostics))
+        .collect::<BTreeMap<_, _>>())
+}
+
+/// `to_sarif` renders the diagnostics as a SARIF 2.1.0 log, with file paths
+/// relative to `base` where possible.
+pub fn to_sarif(files: &BTreeMap<PathBuf, Vec<Diagnostic>>, base: &Path) -> Value {
+    let mut rules = BTreeMap::new();
+    let mut results = vec![];
+
+    for (file, diagnostics) in files {
+        let uri = file.strip_prefix(base).unwrap_or(file);
+        let uri = uri.display().to_string().replace('\\', "/");
+
+        for d in diagnostics {
+            let rule_id = match &d.code {
+                Some(NumberOrString::String(check)) => check.clone(),
+                _ => continue,
+            };
+            rules.entry(rule_id.clone()).or_insert_with(|| {
+                let mut rule = json!({ "id": rule_id });
+                if let Some(href) = d.code_description.as_ref().map(|c| &c.href) {
+                    rule["helpUri"] = json!(href.as_str());
+                }
+                rule
+            });
+
+            let level = match d.severity {
+                Some(DiagnosticSeverity::ERROR) => "error",
+                Some(DiagnosticSeverity::WARNING) => "warning",
+                _ => "note",
+            };
+            results.push(json!({
+                "ruleId": rule_id,
+                "level": level,
+                "message": { "text": d.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri },
+                        "region": {
+                            "startLine": d.range.start.line + 1,
+                            "startColumn": d.range.start.character + 1,
+                            "endLine": d.range.end.line + 1,
+                            "endColumn": d.range.end.character + 1,
+                        },
+                    },
+                }],
+            }));
+        }
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "vale-ls",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/errata-ai/vale-ls",
+                    "rules": rules.into_values().collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sarif() {
+        let mut diagnostic = Diagnostic::new_simple(
+            Range::new(Position::new(2, 4), Position::new(2, 9)),
+            "Use 'use' instead of 'utilize'.".to_string(),
+        );
+        diagnostic.code = Some(NumberOrString::String("Vale.Terms".to_string()));
+        diagnostic.severity = Some(DiagnosticSeverity::WARNING);
+
+        let mut files = BTreeMap::new();
+        files.insert(PathBuf::from("/repo/docs/index.md"), vec![diagnostic]);
+
+        let log = to_sarif(&files, Path::new("/repo"));
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "Vale.Terms");
+        assert_eq!(result["level"], "warning");
+
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "docs/index.md");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(location["region"]["startColumn"], 5);
+        assert_eq!(
+            log["runs"][0]["tool"]["driver"]["rules"][0]["id"],
+            "Vale.Terms"
+        );
+        assert!(!has_errors(&files));
+    }
+}
@@ -1,50 +1,273 @@
-use dashmap::DashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use dashmap::{DashMap, DashSet};
 use ropey::Rope;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tower_lsp::jsonrpc::Result;
+use tempfile::NamedTempFile;
+use tokio::sync::Semaphore;
+use tokio::task::AbortHandle;
+use tower_lsp::jsonrpc::{self, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
+use crate::error::Error;
+use crate::fixture;
+use crate::http;
 use crate::ini;
+use crate::logging;
+use crate::pkg;
+use crate::regex101;
+use crate::settings::{self, RunMode};
 use crate::styles;
 use crate::utils;
 use crate::vale;
+use crate::vocab;
 use crate::yml;
 
 #[derive(Debug, Clone)]
 struct TextDocumentItem {
     uri: Url,
     text: String,
+    /// The version of the document `text` is from, if the client sent one.
+    version: Option<i32>,
+}
+
+/// The `data` of a quick fix whose edit is computed in `codeAction/resolve`.
+#[derive(Debug, Serialize, Deserialize)]
+struct FixData {
+    uri: Url,
+    alert: vale::ValeAlert,
+    fix: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkspaceDiagnosticProgress {
+    token: ProgressToken,
+    value: WorkspaceDiagnosticReportPartialResult,
+}
+
+/// `$/progress` notification carrying a batch of workspace diagnostics.
+enum WorkspaceDiagnosticPartial {}
+
+impl notification::Notification for WorkspaceDiagnosticPartial {
+    type Params = WorkspaceDiagnosticProgress;
+    const METHOD: &'static str = "$/progress";
+}
+
+/// The server state reported to clients through `$/vale/status`, for
+/// extensions that show it in a status bar.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValeStatus {
+    /// The version of the Vale binary in use, if it's installed.
+    pub version: Option<String>,
+    /// The config file used by the most recent lint.
+    pub config_path: Option<String>,
+    /// How long the most recent lint took, in milliseconds.
+    pub last_lint_duration: Option<u64>,
+    /// Whether `vale sync` is running.
+    pub syncing: bool,
+}
+
+/// Used to give each work done progress report a unique token.
+static PROGRESS_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The parameters of a `vale/stats` request.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+/// The parameters of a `vale/stylesPath` request. The document, if given,
+/// selects the config (and so the `StylesPath`) to use.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StylesPathParams {
+    pub text_document: Option<TextDocumentIdentifier>,
+}
+
+/// The parameters of a `vale/testRule` request: the rule to test, the text to
+/// test it against, and, optionally, the extension of the file type to treat
+/// the text as (`md` by default).
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRuleParams {
+    pub text_document: TextDocumentIdentifier,
+    pub text: String,
+    pub extension: Option<String>,
+}
+
+/// The counts and readability scores returned by `vale/stats`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentStats {
+    pub characters: u64,
+    pub words: u64,
+    pub sentences: u64,
+    pub paragraphs: u64,
+    pub syllables: u64,
+    pub complex_words: u64,
+    pub long_words: u64,
+    pub polysyllabic_words: u64,
+    pub words_per_sentence: f64,
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid: f64,
+    pub gunning_fog: f64,
+    pub smog: f64,
+    pub coleman_liau: f64,
+    pub automated_readability: f64,
+}
+
+/// `$/vale/status` notification carrying a `ValeStatus`.
+enum StatusNotification {}
+
+impl notification::Notification for StatusNotification {
+    type Params = ValeStatus;
+    const METHOD: &'static str = "$/vale/status";
+}
+
+/// The diagnostics of a lint, along with what they were computed from.
+#[derive(Debug, Clone)]
+pub struct CachedLint {
+    /// A hash of the document's text and the options Vale was run with.
+    hash: u64,
+    /// When the config file was last modified.
+    modified: Option<SystemTime>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// The parts of the client's capabilities that change how we respond,
+/// recorded during `initialize`.
+///
+/// NOTE: These are kept apart from `param_map` so that no setting can
+/// override them.
+#[derive(Debug, Clone)]
+pub struct ClientCaps {
+    pub pull_diagnostics: bool,
+    pub configuration: bool,
+    pub code_action_resolve: bool,
+    pub watch_files: bool,
+    pub progress: bool,
+    pub inlay_hint_refresh: bool,
+    pub snippets: bool,
+    pub show_document: bool,
+    pub position_encoding: PositionEncodingKind,
+}
+
+impl ClientCaps {
+    fn new(caps: &ClientCapabilities) -> Self {
+        let text_document = caps.text_document.as_ref();
+        let workspace = caps.workspace.as_ref();
+        ClientCaps {
+            pull_diagnostics: text_document.is_some_and(|t| t.diagnostic.is_some()),
+            configuration: workspace.and_then(|w| w.configuration).unwrap_or(false),
+            code_action_resolve: text_document
+                .and_then(|t| t.code_action.as_ref())
+                .and_then(|c| c.resolve_support.as_ref())
+                .is_some_and(|r| r.properties.iter().any(|p| p == "edit")),
+            watch_files: workspace
+                .and_then(|w| w.did_change_watched_files)
+                .and_then(|w| w.dynamic_registration)
+                .unwrap_or(false),
+            progress: caps
+                .window
+                .as_ref()
+                .and_then(|w| w.work_done_progress)
+                .unwrap_or(false),
+            inlay_hint_refresh: workspace
+                .and_then(|w| w.inlay_hint.as_ref())
+                .and_then(|h| h.refresh_support)
+                .unwrap_or(false),
+            snippets: text_document
+                .and_then(|t| t.completion.as_ref())
+                .and_then(|c| c.completion_item.as_ref())
+                .and_then(|i| i.snippet_support)
+                .unwrap_or(false),
+            show_document: caps
+                .window
+                .as_ref()
+                .and_then(|w| w.show_document.as_ref())
+                .is_some_and(|s| s.support),
+            position_encoding: utils::negotiate_encoding(caps),
+        }
+    }
 }
 
-#[derive(Debug)]
+/// The size of the lint pool and the semaphore that enforces it.
+pub type LintSlots = (usize, Arc<Semaphore>);
+
+/// The server's state, which is shared with the tasks it spawns (e.g., a
+/// delayed lint).
+#[derive(Debug, Clone)]
 pub struct Backend {
     pub client: Client,
-    pub document_map: DashMap<String, Rope>,
-    pub param_map: DashMap<String, Value>,
+    pub document_map: Arc<DashMap<String, Rope>>,
+    pub param_map: Arc<DashMap<String, Value>>,
+    pub client_caps: Arc<OnceLock<ClientCaps>>,
+    /// The pending lint of each document (see `schedule_lint`).
+    pub debounce_map: Arc<DashMap<String, AbortHandle>>,
+    pub generation_map: Arc<DashMap<String, u64>>,
+    pub language_map: Arc<DashMap<String, String>>,
+    pub diagnostic_map: Arc<DashMap<String, Vec<Diagnostic>>>,
+    pub published_map: Arc<DashMap<String, Vec<Diagnostic>>>,
+    pub linted_map: Arc<DashMap<String, Rope>>,
+    pub lint_cache: Arc<DashMap<String, CachedLint>>,
+    /// The documents we've told the user are too large to lint.
+    pub large_files: Arc<DashSet<String>>,
+    /// Limits how many Vale processes run at once (see `lint_slots`).
+    pub lint_slots: Arc<RwLock<Option<LintSlots>>>,
+    pub version_map: Arc<DashMap<String, i32>>,
+    /// The results of `cli.testStyle`, by rule URI.
+    pub fixture_map: Arc<DashMap<String, Vec<Diagnostic>>>,
+    pub styles_map: Arc<DashMap<PathBuf, Arc<styles::StylesPath>>>,
+    pub ignores: Arc<utils::Ignores>,
+    pub status: Arc<RwLock<ValeStatus>>,
     pub cli: vale::ValeManager,
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
-        // TODO: Workspace folders / settings
-        let mut cwd = "".to_string();
-        if params.root_uri.is_some() {
-            let path = params.root_uri.unwrap().to_file_path();
-            if path.is_ok() {
-                cwd = path.unwrap().to_str().unwrap().to_string();
-            }
-        }
+        // TODO: Settings
+        let cwd = params
+            .root_uri
+            .as_ref()
+            .and_then(utils::file_path)
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
 
         self.param_map
             .insert("root".to_string(), Value::String(cwd.clone()));
 
+        let folders: Vec<Value> = params
+            .workspace_folders
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|f| utils::file_path(&f.uri))
+            .map(|p| Value::String(p.display().to_string()))
+            .collect();
+        self.param_map
+            .insert("workspaceFolders".to_string(), Value::Array(folders));
+
+        let caps = ClientCaps::new(&params.capabilities);
+        let encoding = caps.position_encoding.clone();
+        let _ = self.client_caps.set(caps);
+
         self.init(params.initialization_options, cwd).await;
         Ok(InitializeResult {
             server_info: None,
-            offset_encoding: None,
+            offset_encoding: Some(encoding.as_str().to_string()),
             capabilities: ServerCapabilities {
+                position_encoding: Some(encoding),
                 text_document_sync: Some(TextDocumentSyncCapability::Options(
                     TextDocumentSyncOptions {
                         open_close: Some(true),
@@ -53,7 +276,7 @@ impl LanguageServer for Backend {
                             include_text: Some(true),
                         })),
                         will_save: None,
-                        will_save_wait_until: None,
+                        will_save_wait_until: Some(true),
                     },
                 )),
                 document_link_provider: Some(DocumentLinkOptions {
@@ -61,35 +284,82 @@ impl LanguageServer for Backend {
                     work_done_progress_options: Default::default(),
                 }),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
+                    DiagnosticOptions {
+                        identifier: Some("vale-ls".to_string()),
+                        inter_file_dependencies: false,
+                        workspace_diagnostics: true,
+                        work_done_progress_options: Default::default(),
+                    },
+                )),
                 execute_command_provider: Some(ExecuteCommandOptions {
-                    commands: vec!["cli.sync".to_string(), "cli.compile".to_string()],
+                    commands: vec![
+                        "cli.sync".to_string(),
+                        "cli.compile".to_string(),
+                        "cli.compileSelection".to_string(),
+                        "cli.runRule".to_string(),
+                        "cli.testStyle".to_string(),
+                        "cli.showConfig".to_string(),
+                        "cli.lintWorkspace".to_string(),
+                        "cli.addToVocab".to_string(),
+                        "cli.lintDocument".to_string(),
+                        "cli.installPackage".to_string(),
+                        "cli.newStyle".to_string(),
+                        "cli.newRule".to_string(),
+                        "cli.openRule".to_string(),
+                        "cli.addToAccept".to_string(),
+                        "cli.addToReject".to_string(),
+                        "cli.uninstall".to_string(),
+                        "cli.installVersion".to_string(),
+                    ],
                     work_done_progress_options: Default::default(),
                 }),
                 completion_provider: Some(CompletionOptions {
                     resolve_provider: Some(false),
-                    trigger_characters: None,
+                    trigger_characters: Some(vec!["[".to_string(), ".".to_string()]),
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
                     completion_item: None,
                 }),
                 code_action_provider: Some(CodeActionProviderCapability::Options(
                     CodeActionOptions {
-                        code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                        code_action_kinds: Some(vec![
+                            CodeActionKind::QUICKFIX,
+                            CodeActionKind::SOURCE_FIX_ALL,
+                        ]),
                         work_done_progress_options: WorkDoneProgressOptions {
                             work_done_progress: None,
                         },
-                        resolve_provider: None,
+                        resolve_provider: Some(true),
                     },
                 )),
                 code_lens_provider: Some(CodeLensOptions {
-                    resolve_provider: Some(true),
+                    resolve_provider: Some(false),
                 }),
                 workspace: Some(WorkspaceServerCapabilities {
                     workspace_folders: Some(WorkspaceFoldersServerCapabilities {
                         supported: Some(true),
                         change_notifications: Some(OneOf::Left(true)),
                     }),
-                    file_operations: None,
+                    file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                        will_rename: Some(FileOperationRegistrationOptions {
+                            filters: vec![FileOperationFilter {
+                                scheme: Some("file".to_string()),
+                                pattern: FileOperationPattern {
+                                    glob: "**/*.yml".to_string(),
+                                    matches: Some(FileOperationPatternKind::File),
+                                    options: None,
+                                },
+                            }],
+                        }),
+                        ..Default::default()
+                    }),
                 }),
                 ..ServerCapabilities::default()
             },
@@ -97,12 +367,25 @@ impl LanguageServer for Backend {
     }
 
     async fn initialized(&self, _: InitializedParams) {
-        if self.should_sync() {
-            self.do_sync().await;
-        }
+        self.pull_settings().await;
+        self.watch_files().await;
+        self.send_status().await;
         self.client
             .log_message(MessageType::INFO, "initialized!")
             .await;
+
+        // Syncing and scanning need Vale, so they wait for the installation
+        // (but nothing else does).
+        let backend = self.clone();
+        tokio::spawn(async move {
+            backend.install().await;
+            if backend.should_sync() {
+                backend.do_sync().await;
+            }
+            if backend.should_scan_workspace() {
+                backend.scan_workspace().await;
+            }
+        });
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -110,27 +393,103 @@ impl LanguageServer for Backend {
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        self.language_map.insert(
+            params.text_document.uri.to_string(),
+            params.text_document.language_id,
+        );
         self.on_change(TextDocumentItem {
             uri: params.text_document.uri,
             text: params.text_document.text,
+            version: Some(params.text_document.version),
         })
         .await
     }
 
     async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
-        self.update(TextDocumentItem {
+        let item = TextDocumentItem {
             uri: params.text_document.uri,
             text: std::mem::take(&mut params.content_changes[0].text),
-        });
+            version: Some(params.text_document.version),
+        };
+        self.update(item.clone());
+
+        if let Some(diagnostics) = self.asset_diagnostics(&item.uri, &item.text) {
+            self.publish(item.uri.clone(), item.version, Ok(diagnostics))
+                .await;
+            return;
+        }
+
+        if self.should_lint_on_change() {
+            self.schedule_lint(item);
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let key = uri.as_str();
+
+        self.document_map.remove(key);
+        self.language_map.remove(key);
+        self.version_map.remove(key);
+        if let Some((_, pending)) = self.debounce_map.remove(key) {
+            pending.abort();
+        }
+        self.diagnostic_map.remove(key);
+        self.linted_map.remove(key);
+        self.lint_cache.remove(key);
+        self.large_files.remove(key);
+        // Any lint still running for the document is now stale.
+        *self.generation_map.entry(uri.to_string()).or_insert(0) += 1;
+
+        if self.published_map.remove(key).is_some()
+            && self.should_clear_on_close()
+            && !self.pull_diagnostics()
+        {
+            self.client.publish_diagnostics(uri, vec![], None).await;
+        }
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        if ini::is_config(params.text_document.uri.path()) {
+            self.invalidate();
+        }
         if params.text.is_some() {
+            let uri = params.text_document.uri;
             self.on_change(TextDocumentItem {
-                uri: params.text_document.uri,
+                uri: uri.clone(),
                 text: params.text.unwrap(),
+                version: self.version(&uri),
             })
-            .await
+            .await;
+            if self.should_apply_fixes_on_save() && self.run_mode() != RunMode::Manual {
+                self.apply_fixes(&uri).await;
+            }
+            // NOTE: Not all clients support refreshing, so we ignore errors.
+            let _ = self.client.code_lens_refresh().await;
+        }
+    }
+
+    async fn will_save_wait_until(
+        &self,
+        params: WillSaveTextDocumentParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        if !self.should_fix_on_will_save() || !self.should_lint(&uri) || !self.cli.is_installed() {
+            return Ok(None);
+        }
+
+        let text = self.document_map.get(uri.as_str()).map(|r| r.to_string());
+        match self.queued(&uri, || self.lint(&uri, text.as_deref())).await {
+            Ok(diagnostics) => {
+                let edits = self.safe_fixes(&uri, &diagnostics);
+                Ok((!edits.is_empty()).then_some(edits))
+            }
+            Err(err) => {
+                self.client
+                    .log_message(MessageType::ERROR, format!("Error: {}", err))
+                    .await;
+                Ok(None)
+            }
         }
     }
 
@@ -138,6 +497,21 @@ impl LanguageServer for Backend {
         match params.command.as_str() {
             "cli.sync" => self.do_sync().await,
             "cli.compile" => self.do_compile(params.arguments).await,
+            "cli.compileSelection" => self.do_compile_selection(params.arguments).await,
+            "cli.runRule" => self.do_run_rule(params.arguments).await,
+            "cli.testStyle" => self.do_test_style(params.arguments).await,
+            "cli.lintWorkspace" => self.do_lint_workspace().await,
+            "cli.addToVocab" => self.do_add_to_vocab(params.arguments).await,
+            "cli.lintDocument" => self.do_lint_document(params.arguments).await,
+            "cli.installPackage" => self.do_install_package(params.arguments).await,
+            "cli.newStyle" => self.do_new_style(params.arguments).await,
+            "cli.newRule" => self.do_new_rule(params.arguments).await,
+            "cli.openRule" => self.do_open_rule(params.arguments).await,
+            "cli.addToAccept" => self.do_add_term(params.arguments, true).await,
+            "cli.addToReject" => self.do_add_term(params.arguments, false).await,
+            "cli.uninstall" => self.do_uninstall().await,
+            "cli.installVersion" => self.do_install_version(params.arguments).await,
+            "cli.showConfig" => return Ok(self.do_show_config(params.arguments).await),
             _ => {}
         };
         Ok(None)
@@ -187,6 +561,11 @@ impl LanguageServer for Backend {
 
                 return Ok(Some(links));
             }
+        } else if ext == "ini" && text.is_some() {
+            let src = text.unwrap().to_string();
+            let mut links = self.vocab_links(&uri, &src);
+            links.append(&mut self.package_links(&src).await);
+            return Ok(Some(links));
         }
 
         Ok(None)
@@ -200,6 +579,9 @@ impl LanguageServer for Backend {
             return Ok(None);
         }
         let pos = params.text_document_position_params.position;
+        if ext.is_empty() {
+            return Ok(self.alert_hover(&uri, pos));
+        }
 
         let rope = self.document_map.get(uri.as_str()).unwrap();
         let span = utils::position_to_range(pos, &rope);
@@ -210,11 +592,16 @@ impl LanguageServer for Backend {
         let range = span.unwrap();
 
         let token = utils::range_to_token(range, &rope);
-        if ext == "ini" && ini::key_to_info(&token).is_some() {
+        let key = match ext.as_str() {
+            "ini" => ini::key_at(&rope.to_string(), pos),
+            _ => None,
+        };
+        let info = key.as_ref().and_then(|(k, _)| ini::key_to_info(k));
+        if let (Some(info), Some((_, range))) = (info, key) {
             return Ok(Some(Hover {
                 contents: HoverContents::Markup(MarkupContent {
                     kind: MarkupKind::Markdown,
-                    value: ini::key_to_info(&token).unwrap().to_string(),
+                    value: info.to_string(),
                 }),
                 range: Some(range),
             }));
@@ -233,45 +620,249 @@ impl LanguageServer for Backend {
                     }));
                 }
             }
+        } else if ext == "ini" {
+            let src = rope.to_string();
+            drop(rope);
+            if let Some(hover) = self.ignore_hover(&src, pos) {
+                return Ok(Some(hover));
+            } else if let Some(hover) = self.check_hover(&uri, &src, pos) {
+                return Ok(Some(hover));
+            }
+            return Ok(self.package_hover(&src, pos).await);
+        }
+
+        Ok(None)
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+
+        let ext = self.get_ext(uri.clone());
+        if ext.is_empty() {
+            let location = self.alert_at(&uri, pos).and_then(|d| match d.code {
+                Some(NumberOrString::String(check)) => self.rule_location(&uri, &check),
+                _ => None,
+            });
+            return Ok(location.map(GotoDefinitionResponse::Scalar));
+        } else if ext == "ini" {
+            return Ok(self.config_definition(&uri, pos));
         }
 
         Ok(None)
     }
 
-    async fn did_change_configuration(&self, _: DidChangeConfigurationParams) {
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let pos = params.text_document_position.position;
+
+        let check = match self.get_ext(uri.clone()).as_str() {
+            "yml" => uri.to_file_path().ok().and_then(|p| check_name(&p)),
+            "ini" => self
+                .document_map
+                .get(uri.as_str())
+                .and_then(|rope| ini::check_at(&rope.to_string(), pos)),
+            _ => None,
+        };
+        let check = match check {
+            Some(check) => check,
+            None => return Ok(None),
+        };
+
+        let mut locations = Vec::new();
+        if params.context.include_declaration {
+            locations.extend(self.rule_location(&uri, &check));
+        }
+        for (config, src) in self.workspace_configs() {
+            locations.extend(
+                ini::check_ranges(&src, &check)
+                    .into_iter()
+                    .map(|range| Location::new(config.clone(), range)),
+            );
+        }
+
+        Ok(Some(locations))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let uri = params.text_document.uri;
+        if self.get_ext(uri.clone()) != "ini" {
+            return Ok(None);
+        }
+
+        Ok(self
+            .document_map
+            .get(uri.as_str())
+            .map(|rope| DocumentSymbolResponse::Nested(ini::symbols(&rope.to_string()))))
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let uri = params.text_document.uri;
+        if self.get_ext(uri.clone()) != "yml" {
+            return Ok(None);
+        }
+
+        Ok(self
+            .document_map
+            .get(uri.as_str())
+            .map(|rope| yml::folding_ranges(&rope.to_string())))
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        // NOTE: Clients may send the whole settings object or only our
+        // section of it.
+        let settings = match params.settings {
+            Value::Object(mut map) if map.contains_key("vale-ls") => map.remove("vale-ls"),
+            settings => Some(settings),
+        };
+        self.parse_params(settings).await;
+        self.pull_settings().await;
+        self.apply_cli().await;
+        self.apply_network().await;
+        self.apply_logging().await;
+        self.invalidate();
+
+        if self.cli.is_installed() {
+            if let Err(err) = self.cli.config(self.config_path(), self.root_path()) {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to load config: {}", err),
+                    )
+                    .await;
+            }
+        }
+
         self.client
             .log_message(MessageType::INFO, "configuration changed!")
             .await;
+        self.relint_all().await;
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let config_changed = params.changes.iter().any(|change| {
+            change
+                .uri
+                .path_segments()
+                .and_then(|mut s| s.next_back())
+                .is_some_and(|name| ini::CONFIG_NAMES.contains(&name))
+        });
+
+        self.invalidate();
+        if config_changed && self.should_sync_on_change() {
+            self.do_sync().await;
+        }
+        self.relint_all().await;
     }
 
-    async fn did_change_workspace_folders(&self, _: DidChangeWorkspaceFoldersParams) {
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        let removed: Vec<PathBuf> = params
+            .event
+            .removed
+            .iter()
+            .filter_map(|f| utils::file_path(&f.uri))
+            .collect();
+
+        let mut folders = self.workspace_roots();
+        folders.retain(|f| !removed.contains(f));
+        for added in params.event.added {
+            if let Some(path) = utils::file_path(&added.uri) {
+                if !folders.contains(&path) {
+                    folders.push(path);
+                }
+            }
+        }
+
+        self.param_map.insert(
+            "workspaceFolders".to_string(),
+            Value::Array(
+                folders
+                    .iter()
+                    .map(|p| Value::String(p.display().to_string()))
+                    .collect(),
+            ),
+        );
         self.client
-            .log_message(MessageType::INFO, "workspace folders changed!")
+            .log_message(
+                MessageType::INFO,
+                format!("workspace folders changed: {} folder(s)", folders.len()),
+            )
             .await;
     }
 
+    async fn will_rename_files(&self, params: RenameFilesParams) -> Result<Option<WorkspaceEdit>> {
+        let renames: Vec<(String, String)> = params
+            .files
+            .iter()
+            .filter_map(|f| self.renamed_check(&f.old_uri, &f.new_uri))
+            .collect();
+        if renames.is_empty() {
+            return Ok(None);
+        }
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (uri, src) in self.workspace_configs() {
+            let edits: Vec<TextEdit> = renames
+                .iter()
+                .flat_map(|(old, new)| ini::rename_check(&src, old, new))
+                .collect();
+            if !edits.is_empty() {
+                changes.insert(uri, edits);
+            }
+        }
+
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(WorkspaceEdit::new(changes)))
+    }
+
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
         let uri = params.text_document_position.text_document.uri;
 
         let ext = self.get_ext(uri.clone());
-        if self.document_map.get(uri.as_str()).is_none() {
-            return Ok(None);
-        }
+        // NOTE: We work on a copy so that the document isn't locked while
+        // Vale runs.
+        let rope = match self.document_map.get(uri.as_str()) {
+            Some(rope) => rope.clone(),
+            None => return Ok(None),
+        };
 
         let position = params.text_document_position.position;
-        let rope = self.document_map.get(uri.as_str()).unwrap();
 
         let context = rope.line(position.line as usize);
         let line = context.as_str().to_owned().unwrap_or("");
 
-        let config = self.cli.config(self.config_path(), self.root_path());
+        if ext == "ini" && line.trim_start().starts_with('[') {
+            let root = self.config_for(&uri).1;
+            let extensions = utils::workspace_extensions(Path::new(&root), 3);
+            return Ok(Some(CompletionResponse::Array(ini::complete_section(
+                position.line,
+                line,
+                extensions,
+            ))));
+        } else if ext.is_empty() {
+            return Ok(self
+                .complete_spelling(&uri, position)
+                .await
+                .map(CompletionResponse::Array));
+        }
+
+        let (config_path, cwd) = self.config_for(&uri);
+        let config = self.cli.config(config_path, cwd);
         if config.is_err() {
             return Ok(None);
         }
 
         let styles = config.unwrap().styles_path;
         match ext.as_str() {
-            "ini" => match ini::complete(line, styles).await {
+            "ini" => match ini::complete(&rope.to_string(), position, styles).await {
                 Ok(computed) => {
                     return Ok(Some(CompletionResponse::Array(computed)));
                 }
@@ -281,10 +872,16 @@ impl LanguageServer for Backend {
                         .await;
                 }
             },
+            "yml" if rope.len_chars() == 0 || rope.to_string().trim().is_empty() => {
+                return Ok(Some(CompletionResponse::Array(yml::templates(
+                    self.client_cap(|c| c.snippets),
+                ))));
+            }
             "yml" => {
                 let rule = yml::Rule::new(uri.to_file_path().unwrap().to_str().unwrap());
                 if rule.is_ok() {
-                    match rule.unwrap().complete(line) {
+                    let block = yml::block(&rope.to_string(), position.line as usize);
+                    match rule.unwrap().complete(line, block) {
                         Ok(computed) => {
                             return Ok(Some(CompletionResponse::Array(computed)));
                         }
@@ -302,13 +899,158 @@ impl LanguageServer for Backend {
         Ok(None)
     }
 
-    async fn code_lens(&self, _: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
-        Ok(None)
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        if self.get_ext(uri.clone()) != "vocab" {
+            return Ok(None);
+        }
+        let rope = match self.document_map.get(uri.as_str()) {
+            Some(rope) => rope.clone(),
+            None => return Ok(None),
+        };
+
+        let src = rope.to_string();
+        let formatted = vocab::normalize(&src);
+        if formatted == src {
+            return Ok(Some(vec![]));
+        }
+
+        let last = rope.len_lines() - 1;
+        let end = rope.line(last);
+        let end = Position::new(
+            last as u32,
+            utils::char_to_column(end, end.len_chars(), &self.position_encoding()),
+        );
+        Ok(Some(vec![TextEdit::new(
+            Range::new(Position::new(0, 0), end),
+            formatted,
+        )]))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let uri = params.text_document.uri;
+        let rope = match self.document_map.get(uri.as_str()) {
+            Some(rope) => rope.clone(),
+            None => return Ok(None),
+        };
+        let diagnostics = match self.diagnostic_map.get(uri.as_str()) {
+            Some(diagnostics) => diagnostics.clone(),
+            None => return Ok(None),
+        };
+
+        let language = match self.language_map.get(uri.as_str()) {
+            Some(language) => language.clone(),
+            None => utils::language_id(uri.path()).to_string(),
+        };
+        let headings = utils::headings(&rope.to_string(), &language);
+        let encoding = self.position_encoding();
+
+        let mut hints = Vec::new();
+        for (i, line) in headings.iter().enumerate() {
+            if (*line as u32) < params.range.start.line || (*line as u32) > params.range.end.line {
+                continue;
+            }
+
+            let end = headings.get(i + 1).copied().unwrap_or(usize::MAX);
+            let in_section: Vec<_> = diagnostics
+                .iter()
+                .filter(|d| (*line..end).contains(&(d.range.start.line as usize)))
+                .collect();
+            let count = |severity| {
+                in_section
+                    .iter()
+                    .filter(|d| d.severity == Some(severity))
+                    .count()
+            };
+
+            let label = match (
+                count(DiagnosticSeverity::ERROR),
+                count(DiagnosticSeverity::WARNING),
+            ) {
+                (0, 0) => continue,
+                (errors, 0) => plural(errors, "error"),
+                (0, warnings) => plural(warnings, "warning"),
+                (errors, warnings) => {
+                    format!(
+                        "{}, {}",
+                        plural(errors, "error"),
+                        plural(warnings, "warning")
+                    )
+                }
+            };
+
+            let text = rope.line(*line);
+            let len = text
+                .to_string()
+                .trim_end_matches(['\r', '\n'])
+                .chars()
+                .count();
+            let position = Position::new(*line as u32, utils::char_to_column(text, len, &encoding));
+
+            hints.push(InlayHint {
+                position,
+                label: InlayHintLabel::String(label),
+                kind: None,
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            });
+        }
+
+        Ok(Some(hints))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = params.text_document.uri;
+
+        let ext = self.get_ext(uri.clone());
+        if ext == "yml" {
+            return Ok(self.rule_lenses(&uri));
+        } else if !ext.is_empty() || !self.cli.is_installed() {
+            return Ok(None);
+        }
+
+        let fp = match uri.to_file_path() {
+            Ok(fp) => fp,
+            Err(_) => return Ok(None),
+        };
+
+        match self.cli.metrics(fp, self.config_for(&uri).0) {
+            Ok(metrics) => Ok(Some(vec![CodeLens {
+                range: Range::default(),
+                command: Some(Command {
+                    title: utils::metrics_summary(&metrics),
+                    command: "".to_string(),
+                    arguments: None,
+                }),
+                data: None,
+            }])),
+            Err(err) => {
+                self.client
+                    .log_message(MessageType::ERROR, format!("Error: {}", err))
+                    .await;
+                Ok(None)
+            }
+        }
     }
 
     async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let wants_fix_all = params.context.only.as_ref().is_some_and(|only| {
+            only.iter()
+                .any(|k| *k == CodeActionKind::SOURCE_FIX_ALL || *k == CodeActionKind::SOURCE)
+        });
+        if wants_fix_all {
+            return Ok(self.fix_all(params.text_document.uri).await);
+        }
+
         if params.context.diagnostics.is_empty() {
-            return Ok(None);
+            let actions = self.compile_actions(&params);
+            if actions.is_empty() {
+                return Ok(None);
+            }
+            return Ok(Some(actions));
         }
 
         let diagnostics = params.context.diagnostics[0].data.as_ref();
@@ -320,213 +1062,3169 @@ impl LanguageServer for Backend {
         }
 
         let s = serde_json::to_string(diagnostics.unwrap()).unwrap();
-        match self.cli.fix(&s) {
-            Ok(fixed) => {
-                let alert: vale::ValeAlert = serde_json::from_str(&s).unwrap();
-                let mut range = utils::alert_to_range(alert.clone());
+        let alert: vale::ValeAlert = match serde_json::from_str(&s) {
+            Ok(alert) => alert,
+            Err(_) => return Ok(None),
+        };
 
-                if !alert.action.name.is_some() {
-                    return Ok(None);
-                }
+        let uri = params.text_document.uri.clone();
+        let diagnostics = params.context.diagnostics.clone();
 
-                let action_name = alert.action.name.unwrap();
-                if action_name == "remove" {
-                    // NOTE: we need to add a character when deleting to avoid
-                    // leaving a double space.
-                    range.end.character += 1;
-                }
+        let lazy = match alert.action.name {
+            Some(_) if self.resolve_code_actions() => self.lazy_fixes(&uri, &alert),
+            _ => None,
+        };
 
-                let mut fixes = vec![];
-                for fix in fixed.suggestions {
-                    fixes.push(CodeActionOrCommand::CodeAction(CodeAction {
-                        title: utils::make_title(
-                            action_name.clone(),
-                            alert.matched.clone(),
-                            fix.clone(),
-                        ),
-                        kind: Some(CodeActionKind::QUICKFIX),
-                        diagnostics: Some(params.context.diagnostics.clone()),
-                        edit: Some(WorkspaceEdit {
-                            changes: Some(
-                                [(
-                                    params.text_document.uri.clone(),
-                                    vec![TextEdit {
-                                        range: range,
-                                        new_text: fix,
-                                    }],
-                                )]
-                                .iter()
-                                .cloned()
-                                .collect(),
-                            ),
-                            ..WorkspaceEdit::default()
-                        }),
-                        ..CodeAction::default()
-                    }));
+        let mut actions = vec![];
+        if let Some(lazy) = lazy {
+            actions.extend(lazy.into_iter().map(|mut action| {
+                action.diagnostics = Some(diagnostics.clone());
+                CodeActionOrCommand::CodeAction(action)
+            }));
+        } else if alert.action.name.is_some() {
+            match self.cli.fix(&s) {
+                Ok(fixed) => {
+                    for fix in fixed.suggestions {
+                        let mut action = self.fix_action(&uri, &alert, fix);
+                        action.diagnostics = Some(diagnostics.clone());
+                        actions.push(CodeActionOrCommand::CodeAction(action));
+                    }
+                }
+                Err(e) => {
+                    self.client
+                        .log_message(MessageType::ERROR, format!("Error: {}", e))
+                        .await;
                 }
-                Ok(Some(fixes))
-            }
-            Err(e) => {
-                self.client
-                    .log_message(MessageType::ERROR, format!("Error: {}", e))
-                    .await;
-                Ok(None)
             }
         }
+
+        actions.append(&mut self.vocab_actions(&params, &alert));
+        if let Some(action) = self.directive_action(&params, &alert) {
+            actions.push(action);
+        }
+        if let Some(action) = self.disable_action(&params, &alert) {
+            actions.push(action);
+        }
+        if let Some(action) = self.open_rule_action(&params, &alert) {
+            actions.push(action);
+        }
+        if actions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(actions))
+    }
+
+    async fn code_action_resolve(&self, action: CodeAction) -> Result<CodeAction> {
+        let data: FixData = match action.data.clone().map(serde_json::from_value) {
+            Some(Ok(data)) => data,
+            _ => return Ok(action),
+        };
+
+        let resolved = self.fix_action(&data.uri, &data.alert, data.fix);
+        Ok(CodeAction {
+            edit: resolved.edit,
+            ..action
+        })
     }
 }
 
 impl Backend {
+    /// `lazy_fixes` returns the alert's quick fixes without their edits, which
+    /// are computed in `codeAction/resolve`.
+    ///
+    /// This only applies to fixes that are known up front (removals and
+    /// replacements with explicit params): anything else needs `vale fix`
+    /// to list its suggestions, so we return `None` and offer them eagerly.
+    fn lazy_fixes(&self, uri: &Url, alert: &vale::ValeAlert) -> Option<Vec<CodeAction>> {
+        let name = alert.action.name.clone().unwrap_or_default();
+        let known: Vec<String> = match name.as_str() {
+            "remove" => vec!["".to_string()],
+            "replace" => alert.action.params.clone().unwrap_or_default(),
+            _ => vec![],
+        };
+        if known.is_empty() {
+            return None;
+        }
+
+        let actions = known
+            .into_iter()
+            .map(|fix| {
+                let title = utils::make_title(name.clone(), alert.matched.clone(), fix.clone());
+                let data = FixData {
+                    uri: uri.clone(),
+                    alert: alert.clone(),
+                    fix,
+                };
+                CodeAction {
+                    title,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    data: serde_json::to_value(data).ok(),
+                    ..CodeAction::default()
+                }
+            })
+            .collect();
+        Some(actions)
+    }
+
+    /// `complete_spelling` offers the suggestions for a misspelling at `pos`,
+    /// so that a correction can be picked while typing.
+    ///
+    /// `vale fix` runs on the blocking pool, so a slow Vale doesn't hold up
+    /// other requests.
+    async fn complete_spelling(&self, uri: &Url, pos: Position) -> Option<Vec<CompletionItem>> {
+        let diagnostic = self.alert_at(uri, pos)?;
+        let alert: vale::ValeAlert = serde_json::from_value(diagnostic.data?).ok()?;
+
+        let is_spelling = alert.action.name.as_deref() == Some("suggest")
+            && alert
+                .action
+                .params
+                .as_ref()
+                .is_some_and(|p| p.iter().any(|p| p == "spellings"));
+        if !is_spelling {
+            return None;
+        }
+
+        let cli = self.cli.clone();
+        let data = serde_json::to_string(&alert).ok()?;
+        let fixed = tokio::task::spawn_blocking(move || cli.fix(&data))
+            .await
+            .ok()?
+            .ok()?;
+        Some(
+            fixed
+                .suggestions
+                .into_iter()
+                .enumerate()
+                .map(|(i, fix)| CompletionItem {
+                    label: fix.clone(),
+                    kind: Some(CompletionItemKind::TEXT),
+                    detail: Some(alert.check.clone()),
+                    sort_text: Some(format!("{:04}", i)),
+                    filter_text: Some(alert.matched.clone()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                        diagnostic.range,
+                        fix,
+                    ))),
+                    ..CompletionItem::default()
+                })
+                .collect(),
+        )
+    }
+
+    /// `fix_action` builds the quick fix that applies `fix` to the alert.
+    fn fix_action(&self, uri: &Url, alert: &vale::ValeAlert, fix: String) -> CodeAction {
+        let rope = self.document_map.get(uri.as_str()).map(|r| r.clone());
+        let title = utils::make_title(
+            alert.action.name.clone().unwrap_or_default(),
+            alert.matched.clone(),
+            fix.clone(),
+        );
+        let edit = utils::alert_to_edit(alert, fix, rope.as_ref(), &self.position_encoding());
+
+        CodeAction {
+            title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            edit: Some(WorkspaceEdit {
+                changes: Some([(uri.clone(), vec![edit])].into_iter().collect()),
+                ..WorkspaceEdit::default()
+            }),
+            ..CodeAction::default()
+        }
+    }
+
     async fn on_change(&self, params: TextDocumentItem) {
         let uri = params.uri.clone();
-        let fp = uri.to_file_path();
+        let fp = utils::file_path(&uri);
 
         let has_cli = self.cli.is_installed();
 
         self.update(params.clone());
-        if has_cli && fp.is_ok() {
-            match self
-                .cli
-                .run(fp.unwrap(), self.config_path(), self.config_filter())
-            {
-                Ok(result) => {
-                    let mut diagnostics = Vec::new();
-                    for (_, v) in result.iter() {
-                        for alert in v {
-                            diagnostics.push(utils::alert_to_diagnostic(alert));
-                        }
-                    }
-                    self.client
-                        .publish_diagnostics(params.uri.clone(), diagnostics, None)
-                        .await;
-                }
-                Err(err) => {
+        if let Some(diagnostics) = self.asset_diagnostics(&uri, &params.text) {
+            self.publish(uri, params.version, Ok(diagnostics)).await;
+            return;
+        } else if self.is_too_large(&uri) && self.large_files.insert(uri.to_string()) {
+            self.client
+                .show_message(
+                    MessageType::INFO,
+                    format!(
+                        "{} is larger than `maxFileSize`, so it won't be linted.",
+                        uri.path_segments()
+                            .and_then(|mut s| s.next_back())
+                            .unwrap_or(uri.as_str())
+                    ),
+                )
+                .await;
+        }
+        if !self.should_lint(&uri) || self.run_mode() == RunMode::Manual {
+            return;
+        }
+
+        if has_cli && fp.is_some() {
+            let result = self.queued(&uri, || self.lint(&uri, None)).await;
+            self.publish(uri.clone(), params.version, result).await;
+        } else if has_cli && self.lint_path(&uri).is_some() {
+            let text = Some(params.text.as_str());
+            let result = self.queued(&uri, || self.lint(&uri, text)).await;
+            self.publish(uri.clone(), params.version, result).await;
+        } else if !has_cli {
+            self.client
+                .log_message(MessageType::WARNING, "Vale CLI not installed!")
+                .await;
+        } else {
+            self.client
+                .log_message(MessageType::INFO, "No file path found. Is the file saved?")
+                .await;
+        }
+    }
+
+    /// `on_edit` lints the unsaved contents of a document, which are passed to
+    /// Vale through stdin.
+    async fn on_edit(&self, params: TextDocumentItem) {
+        if self.should_lint(&params.uri)
+            && self.cli.is_installed()
+            && self.lint_path(&params.uri).is_some()
+        {
+            let result = self
+                .queued(&params.uri, || self.lint(&params.uri, Some(&params.text)))
+                .await;
+            self.publish(params.uri, params.version, result).await;
+        }
+    }
+
+    /// `on_region_edit` is `on_edit` for large documents: it only lints the
+    /// sections that changed since the last run.
+    async fn on_region_edit(&self, params: TextDocumentItem) {
+        if self.should_lint(&params.uri)
+            && self.cli.is_installed()
+            && self.lint_path(&params.uri).is_some()
+        {
+            let result = self
+                .queued(&params.uri, || self.lint_region(&params.uri, &params.text))
+                .await;
+            self.publish(params.uri, params.version, result).await;
+        }
+    }
+
+    /// `asset_diagnostics` checks config, rule, and vocabulary files, which we
+    /// validate ourselves instead of running Vale on them.
+    fn asset_diagnostics(&self, uri: &Url, text: &str) -> Option<Vec<Diagnostic>> {
+        match self.get_ext(uri.clone()).as_str() {
+            "ini" => {
+                let mut diagnostics = ini::diagnose(text);
+                let (config_path, cwd) = self.config_for(uri);
+                if let Ok(config) = self.cli.config(config_path, cwd) {
+                    let styles = self.styles(config.styles_path);
+                    diagnostics.extend(ini::diagnose_rules(text, &styles));
+                }
+                Some(diagnostics)
+            }
+            "yml" => {
+                let mut diagnostics = yml::diagnose(text);
+                if let Some(results) = self.fixture_map.get(uri.as_str()) {
+                    diagnostics.extend(results.iter().cloned());
+                }
+                Some(diagnostics)
+            }
+            "vocab" => {
+                let (name, path) = vocab::sibling(Path::new(uri.path()))?;
+                let other = Url::from_file_path(&path)
+                    .ok()
+                    .and_then(|u| self.document_map.get(u.as_str()).map(|r| r.to_string()))
+                    .or_else(|| std::fs::read_to_string(&path).ok());
+                Some(vocab::diagnose(text, other.as_deref().map(|o| (name, o))))
+            }
+            _ => None,
+        }
+    }
+
+    /// `relint` lints an open document again using its current contents.
+    async fn relint(&self, uri: &Url) {
+        let text = self.document_map.get(uri.as_str()).map(|r| r.to_string());
+        if let Some(text) = text {
+            self.on_edit(TextDocumentItem {
+                uri: uri.clone(),
+                text,
+                version: self.version(uri),
+            })
+            .await;
+        }
+    }
+
+    /// `relint_all` refreshes the diagnostics of every open document, such as
+    /// after the settings change.
+    async fn relint_all(&self) {
+        if self.pull_diagnostics() {
+            let _ = self
+                .client
+                .send_request::<request::WorkspaceDiagnosticRefresh>(())
+                .await;
+            return;
+        }
+
+        let uris: Vec<Url> = self
+            .document_map
+            .iter()
+            .filter_map(|entry| Url::parse(entry.key()).ok())
+            .collect();
+        for uri in uris {
+            let text = match self.document_map.get(uri.as_str()) {
+                Some(rope) => rope.to_string(),
+                None => continue,
+            };
+            match self.asset_diagnostics(&uri, &text) {
+                Some(diagnostics) => {
+                    let version = self.version(&uri);
+                    self.publish(uri, version, Ok(diagnostics)).await
+                }
+                None if self.run_mode() == RunMode::Manual => {}
+                None => self.relint(&uri).await,
+            }
+        }
+    }
+
+    /// `diagnostic` handles `textDocument/diagnostic` requests from clients
+    /// that prefer to pull diagnostics rather than have them pushed.
+    pub async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        let uri = params.text_document.uri;
+
+        let mut items = Vec::new();
+        let text = self.document_map.get(uri.as_str()).map(|r| r.to_string());
+        if let Some(diagnostics) = text.and_then(|t| self.asset_diagnostics(&uri, &t)) {
+            items = diagnostics;
+        } else if self.run_mode() == RunMode::Manual {
+            // Only `cli.lintDocument` runs Vale; report what it last found.
+            if let Some(diagnostics) = self.diagnostic_map.get(uri.as_str()) {
+                items = diagnostics.clone();
+            }
+        } else if self.should_lint(&uri)
+            && self.cli.is_installed()
+            && self.lint_path(&uri).is_some()
+        {
+            let text = self.document_map.get(uri.as_str()).map(|r| r.to_string());
+            let result = self.queued(&uri, || self.lint(&uri, text.as_deref())).await;
+
+            // NOTE: Running Vale blocks, so this gives a `$/cancelRequest` that
+            // arrived in the meantime a chance to take effect.
+            tokio::task::yield_now().await;
+
+            self.send_status().await;
+            match result {
+                Ok(diagnostics) => items = diagnostics,
+                Err(Error::Stale) => {
+                    return Err(jsonrpc::Error {
+                        code: jsonrpc::ErrorCode::ServerError(-32802),
+                        message: Error::Stale.to_string(),
+                        data: Some(serde_json::json!({ "retriggerRequest": true })),
+                    });
+                }
+                Err(err) => {
                     self.client
                         .log_message(MessageType::ERROR, format!("Parsing error: {:?}", err))
                         .await;
-                    match serde_json::from_str::<vale::ValeError>(&err.to_string()) {
-                        Ok(parsed) => {
-                            self.client.show_message(MessageType::ERROR, parsed).await;
-                        }
-                        Err(e) => {
-                            self.client.show_message(MessageType::ERROR, e).await;
-                        }
-                    };
                 }
             }
-        } else if !has_cli {
-            self.client
-                .log_message(MessageType::WARNING, "Vale CLI not installed!")
-                .await;
-        } else {
+        }
+
+        Ok(DocumentDiagnosticReportResult::Report(
+            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: None,
+                    items,
+                },
+            }),
+        ))
+    }
+
+    /// `workspace_diagnostic` handles `workspace/diagnostic` requests by
+    /// running Vale over each workspace folder.
+    ///
+    /// If the client provides a partial result token, each folder's report is
+    /// streamed as soon as it's ready.
+    pub async fn workspace_diagnostic(
+        &self,
+        params: WorkspaceDiagnosticParams,
+    ) -> Result<WorkspaceDiagnosticReportResult> {
+        let token = params.partial_result_params.partial_result_token;
+
+        let mut items = Vec::new();
+        if self.cli.is_installed() {
+            for folder in self.workspace_roots() {
+                let mut reports: Vec<WorkspaceDocumentDiagnosticReport> = match self
+                    .lint_dir_queued(folder)
+                    .await
+                {
+                    Ok(files) => files
+                        .into_iter()
+                        .map(|(uri, items)| {
+                            WorkspaceDocumentDiagnosticReport::Full(
+                                WorkspaceFullDocumentDiagnosticReport {
+                                    uri,
+                                    version: None,
+                                    full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                                        result_id: None,
+                                        items,
+                                    },
+                                },
+                            )
+                        })
+                        .collect(),
+                    Err(err) => {
+                        self.client
+                            .log_message(MessageType::ERROR, format!("Parsing error: {:?}", err))
+                            .await;
+                        continue;
+                    }
+                };
+
+                if let Some(token) = token.clone() {
+                    self.client
+                        .send_notification::<WorkspaceDiagnosticPartial>(
+                            WorkspaceDiagnosticProgress {
+                                token,
+                                value: WorkspaceDiagnosticReportPartialResult { items: reports },
+                            },
+                        )
+                        .await;
+                } else {
+                    items.append(&mut reports);
+                }
+            }
+        }
+
+        Ok(WorkspaceDiagnosticReportResult::Report(
+            WorkspaceDiagnosticReport { items },
+        ))
+    }
+
+    /// `stats` handles `vale/stats` requests, returning the word counts and
+    /// readability scores Vale computes for a saved document.
+    pub async fn stats(&self, params: StatsParams) -> Result<Option<DocumentStats>> {
+        let uri = params.text_document.uri;
+        let fp = match uri.to_file_path() {
+            Ok(fp) => fp,
+            Err(_) => return Ok(None),
+        };
+        let result = match self.cli.is_installed() {
+            true => self.cli.metrics(fp, self.config_for(&uri).0),
+            false => Err(Error::from("Vale CLI not installed!")),
+        };
+        let m = result.map_err(|err| jsonrpc::Error {
+            code: jsonrpc::ErrorCode::InternalError,
+            message: err.to_string(),
+            data: None,
+        })?;
+
+        Ok(Some(DocumentStats {
+            characters: m.characters as u64,
+            words: m.words as u64,
+            sentences: m.sentences as u64,
+            paragraphs: m.paragraphs as u64,
+            syllables: m.syllables as u64,
+            complex_words: m.complex_words as u64,
+            long_words: m.long_words as u64,
+            polysyllabic_words: m.polysyllabic_words as u64,
+            words_per_sentence: m.words_per_sentence(),
+            flesch_reading_ease: m.flesch_reading_ease(),
+            flesch_kincaid: m.flesch_kincaid(),
+            gunning_fog: m.gunning_fog(),
+            smog: m.smog(),
+            coleman_liau: m.coleman_liau(),
+            automated_readability: m.automated_readability(),
+        }))
+    }
+
+    /// `styles_path` handles `vale/stylesPath` requests, returning the styles,
+    /// rules, and vocabularies in the `StylesPath`.
+    pub async fn styles_path(&self, params: StylesPathParams) -> Result<Option<styles::Tree>> {
+        let (config_path, cwd) = match params.text_document {
+            Some(doc) => self.config_for(&doc.uri),
+            None => (self.config_path(), self.root_path()),
+        };
+
+        let tree = self
+            .cli
+            .config(config_path, cwd)
+            .and_then(|config| self.styles(config.styles_path).tree());
+        match tree {
+            Ok(tree) => Ok(Some(tree)),
+            Err(err) => Err(jsonrpc::Error {
+                code: jsonrpc::ErrorCode::InternalError,
+                message: err.to_string(),
+                data: None,
+            }),
+        }
+    }
+
+    /// `test_rule` handles `vale/testRule` requests, returning the diagnostics
+    /// that a rule, as it currently reads in the editor, reports for some
+    /// sample text.
+    pub async fn test_rule(&self, params: TestRuleParams) -> Result<Vec<Diagnostic>> {
+        let uri = params.text_document.uri;
+        let ext = params.extension.unwrap_or("md".to_string());
+
+        let result = uri
+            .to_file_path()
+            .ok()
+            .and_then(|fp| {
+                let check = check_name(&fp)?;
+                let rule = match self.document_map.get(uri.as_str()) {
+                    Some(rope) => rope.to_string(),
+                    None => std::fs::read_to_string(&fp).ok()?,
+                };
+                Some((check, rule))
+            })
+            .ok_or(Error::from(format!("Unable to read a rule from {}.", uri)))
+            .and_then(|(check, rule)| {
+                let ext = ext.trim_start_matches('.');
+                self.cli.test_rule(&check, &rule, &params.text, ext)
+            });
+
+        let alerts = result.map_err(|err| jsonrpc::Error {
+            code: jsonrpc::ErrorCode::InternalError,
+            message: err.to_string(),
+            data: None,
+        })?;
+
+        let rope = Rope::from_str(&params.text);
+        let encoding = self.position_encoding();
+        Ok(alerts
+            .iter()
+            .map(|alert| utils::alert_to_diagnostic(alert, Some(&rope), &encoding, None))
+            .collect())
+    }
+
+    /// `lint_dir` runs Vale over a directory and returns the diagnostics for
+    /// each file with alerts.
+    ///
+    /// Each folder `configPaths` maps under `dir` is linted separately, with
+    /// its own config, and its files are left out of the results of the
+    /// enclosing run so that they match what linting them one by one gives.
+    fn lint_dir(&self, dir: PathBuf) -> std::result::Result<Vec<(Url, Vec<Diagnostic>)>, Error> {
+        let mut runs: Vec<PathBuf> = self
+            .config_paths()
+            .into_iter()
+            .map(|(folder, _)| folder)
+            .filter(|folder| folder.starts_with(&dir) && *folder != dir && folder.is_dir())
+            .collect();
+        runs.sort();
+        runs.dedup();
+        runs.insert(0, dir);
+
+        let encoding = self.position_encoding();
+        let severities = self.get_setting("severities");
+
+        let mut files = Vec::new();
+        for run in runs {
+            let (folder, config_path) = match self.mapping(&run) {
+                Some((folder, config)) => (Some(folder), config.display().to_string()),
+                None => (None, self.config_path()),
+            };
+            let result = self.cli.run_dir(run, config_path, self.config_filter())?;
+
+            for (path, alerts) in result.iter() {
+                let fp = Path::new(path);
+                if self.mapping(fp).map(|(f, _)| f) != folder {
+                    // This file belongs to a more specific folder's run.
+                    continue;
+                }
+                let uri = match Url::from_file_path(fp) {
+                    Ok(uri) if self.should_lint(&uri) => uri,
+                    _ => continue,
+                };
+
+                let rope = self
+                    .document_map
+                    .get(uri.as_str())
+                    .map(|r| r.clone())
+                    .or_else(|| Rope::from_reader(std::fs::File::open(path).ok()?).ok());
+                let mut diagnostics: Vec<Diagnostic> = alerts
+                    .iter()
+                    .map(|alert| {
+                        utils::alert_to_diagnostic(
+                            alert,
+                            rope.as_ref(),
+                            &encoding,
+                            severities.as_ref(),
+                        )
+                    })
+                    .collect();
+                self.link_rules(&uri, &mut diagnostics);
+                files.push((uri, diagnostics));
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// `lint_dir_queued` runs `lint_dir` once a lint slot is free, on the
+    /// blocking pool so that a large directory doesn't hold up other requests.
+    async fn lint_dir_queued(
+        &self,
+        dir: PathBuf,
+    ) -> std::result::Result<Vec<(Url, Vec<Diagnostic>)>, Error> {
+        let _permit = self.lint_slots().acquire_owned().await;
+        let backend = self.clone();
+        tokio::task::spawn_blocking(move || backend.lint_dir(dir))
+            .await
+            .map_err(|e| Error::from(e.to_string()))?
+    }
+
+    /// `vocab_actions` offers to add the alert's match to each of the active
+    /// Vocabs, for spelling and terminology alerts.
+    fn vocab_actions(
+        &self,
+        params: &CodeActionParams,
+        alert: &vale::ValeAlert,
+    ) -> CodeActionResponse {
+        let lists = utils::vocab_lists(&alert.check);
+        if lists.is_empty() {
+            return vec![];
+        }
+
+        let (config_path, cwd) = self.config_for(&params.text_document.uri);
+        let config = match self.cli.config(config_path, cwd) {
+            Ok(config) => config,
+            Err(_) => return vec![],
+        };
+
+        let mut actions = vec![];
+        for vocab in config.vocab {
+            for list in lists.iter() {
+                let title = format!("Add ‘{}’ to Vocab ‘{}’ ({})", alert.matched, vocab, list);
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: title.clone(),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(params.context.diagnostics.clone()),
+                    command: Some(Command {
+                        title,
+                        command: "cli.addToVocab".to_string(),
+                        arguments: Some(vec![
+                            Value::String(params.text_document.uri.to_string()),
+                            Value::String(vocab.clone()),
+                            Value::String(alert.matched.clone()),
+                            Value::String(list.to_string()),
+                        ]),
+                    }),
+                    ..CodeAction::default()
+                }));
+            }
+        }
+
+        actions
+    }
+
+    /// `directive_action` offers to suppress a single alert by wrapping it in
+    /// comments that turn its check off and on again.
+    fn directive_action(
+        &self,
+        params: &CodeActionParams,
+        alert: &vale::ValeAlert,
+    ) -> Option<CodeActionOrCommand> {
+        let uri = params.text_document.uri.clone();
+        let rope = self.document_map.get(uri.as_str())?;
+
+        let ext = uri.path().rsplit('.').next().unwrap_or("").to_lowercase();
+        let edits = utils::directive_edits(&ext, alert, &rope, &self.position_encoding());
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Ignore ‘{}’ here", alert.check),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(params.context.diagnostics.clone()),
+            edit: Some(WorkspaceEdit {
+                changes: Some([(uri, edits)].into_iter().collect()),
+                ..WorkspaceEdit::default()
+            }),
+            ..CodeAction::default()
+        }))
+    }
+
+    /// `disable_action` offers to turn the alert's check off in the config
+    /// file that applies to the document.
+    fn disable_action(
+        &self,
+        params: &CodeActionParams,
+        alert: &vale::ValeAlert,
+    ) -> Option<CodeActionOrCommand> {
+        let fp = params.text_document.uri.to_file_path().ok()?;
+        let config = self.config_file(&fp)?;
+        let target = Url::from_file_path(&config).ok()?;
+
+        let src = match self.document_map.get(target.as_str()) {
+            Some(rope) => rope.to_string(),
+            None => std::fs::read_to_string(&config).ok()?,
+        };
+        let relative = config
+            .parent()
+            .and_then(|dir| fp.strip_prefix(dir).ok())
+            .unwrap_or(&fp);
+        let edit = ini::set_rule(&src, relative, &alert.check, "NO");
+
+        let name = config.file_name()?.to_string_lossy().to_string();
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Disable ‘{}’ in {}", alert.check, name),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(params.context.diagnostics.clone()),
+            edit: Some(WorkspaceEdit {
+                changes: Some([(target, vec![edit])].into_iter().collect()),
+                ..WorkspaceEdit::default()
+            }),
+            ..CodeAction::default()
+        }))
+    }
+
+    /// `alert_hover` describes the rule behind the alert at `pos`, using its
+    /// YAML definition when one exists in the `StylesPath`.
+    fn alert_hover(&self, uri: &Url, pos: Position) -> Option<Hover> {
+        let diagnostic = self.alert_at(uri, pos)?;
+        let alert: vale::ValeAlert = serde_json::from_value(diagnostic.data?).ok()?;
+        let value = match self.rule_file(uri, &alert.check) {
+            Some(path) => yml::Rule::new(path.to_str()?).ok()?.summary(&alert.check),
+            None => {
+                let mut info = format!(
+                    "**{}** (`{}`)\n\n{}",
+                    alert.check, alert.severity, alert.message
+                );
+                if !alert.description.is_empty() {
+                    info += &format!("\n\n{}", alert.description);
+                }
+                if !alert.link.is_empty() {
+                    info += &format!("\n\n[Documentation]({})", alert.link);
+                }
+                info
+            }
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(diagnostic.range),
+        })
+    }
+
+    /// `alert_at` returns the most recent diagnostic covering `pos`.
+    fn alert_at(&self, uri: &Url, pos: Position) -> Option<Diagnostic> {
+        self.diagnostic_map
+            .get(uri.as_str())?
+            .iter()
+            .find(|d| d.range.start <= pos && pos <= d.range.end)
+            .cloned()
+    }
+
+    /// `link_rules` points each diagnostic at the YAML file of the rule that
+    /// raised it, through its related information.
+    fn link_rules(&self, uri: &Url, diagnostics: &mut [Diagnostic]) {
+        let mut locations: HashMap<String, Option<Location>> = HashMap::new();
+        for d in diagnostics.iter_mut() {
+            let check = match &d.code {
+                Some(NumberOrString::String(check)) => check.clone(),
+                _ => continue,
+            };
+            let location = locations
+                .entry(check.clone())
+                .or_insert_with(|| self.rule_location(uri, &check))
+                .clone();
+            d.related_information = location.map(|location| {
+                vec![DiagnosticRelatedInformation {
+                    location,
+                    message: format!("defined by {}", check),
+                }]
+            });
+        }
+    }
+
+    /// `rule_location` returns the `message` line of the YAML file that
+    /// defines `check`.
+    fn rule_location(&self, uri: &Url, check: &str) -> Option<Location> {
+        let path = self.rule_file(uri, check)?;
+        rule_location(path)
+    }
+
+    /// `compile_actions` offers to compile the pattern selected in a rule's
+    /// `tokens` or `swap`, on its own.
+    fn compile_actions(&self, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let uri = &params.text_document.uri;
+        let range = params.range;
+        if !uri.path().ends_with(".yml")
+            || range.start.line != range.end.line
+            || range.start.character == range.end.character
+        {
+            return vec![];
+        }
+
+        let src = match self.document_map.get(uri.as_str()) {
+            Some(rope) => rope.to_string(),
+            None => return vec![],
+        };
+        match yml::block(&src, range.start.line as usize) {
+            Some((key, _)) if key == "tokens" || key == "swap" => {}
+            _ => return vec![],
+        }
+
+        let mut titles = vec![("Compile selection", false)];
+        if !http::is_offline() {
+            titles.push(("Open selection on Regex101", true));
+        }
+        titles
+            .into_iter()
+            .map(|(title, upload)| {
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: title.to_string(),
+                    command: Some(Command {
+                        title: title.to_string(),
+                        command: "cli.compileSelection".to_string(),
+                        arguments: Some(vec![
+                            Value::String(uri.to_string()),
+                            serde_json::to_value(range).unwrap_or_default(),
+                            Value::Bool(upload),
+                        ]),
+                    }),
+                    ..CodeAction::default()
+                })
+            })
+            .collect()
+    }
+
+    /// `open_rule_action` offers to open the YAML file that defines the
+    /// alert's check, if it isn't built into Vale.
+    fn open_rule_action(
+        &self,
+        params: &CodeActionParams,
+        alert: &vale::ValeAlert,
+    ) -> Option<CodeActionOrCommand> {
+        self.rule_file(&params.text_document.uri, &alert.check)?;
+
+        let title = format!("Open rule ‘{}’", alert.check);
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: title.clone(),
+            diagnostics: Some(params.context.diagnostics.clone()),
+            command: Some(Command {
+                title,
+                command: "cli.openRule".to_string(),
+                arguments: Some(vec![
+                    Value::String(alert.check.clone()),
+                    Value::String(params.text_document.uri.to_string()),
+                ]),
+            }),
+            ..CodeAction::default()
+        }))
+    }
+
+    /// `do_open_rule` opens the YAML file that defines a check, such as
+    /// `Microsoft.Passive`, given the check and, optionally, a document URI
+    /// to resolve the config from.
+    async fn do_open_rule(&self, arguments: Vec<Value>) {
+        let args: Vec<&str> = arguments.iter().filter_map(|a| a.as_str()).collect();
+        let check = match args.first() {
+            Some(check) => check.to_string(),
+            None => {
+                self.client
+                    .show_message(MessageType::ERROR, "No rule provided. Please try again.")
+                    .await;
+                return;
+            }
+        };
+
+        let location = match args.get(1).and_then(|u| Url::parse(u).ok()) {
+            Some(uri) => self.rule_location(&uri, &check),
+            None => self
+                .cli
+                .config(self.config_path(), self.root_path())
+                .ok()
+                .and_then(|c| self.styles(c.styles_path).rule_path(&check))
+                .and_then(rule_location),
+        };
+        let location = match location {
+            Some(location) => location,
+            None => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("No rule file found for '{}'.", check),
+                    )
+                    .await;
+                return;
+            }
+        };
+
+        self.show_document(location.uri, Some(location.range)).await;
+    }
+
+    /// `show_document` asks the client to open `uri`, selecting `range`.
+    ///
+    /// Not all clients support `window/showDocument`, so we otherwise (or if
+    /// it fails) tell the user where to find the file.
+    async fn show_document(&self, uri: Url, range: Option<Range>) {
+        if self.client_cap(|c| c.show_document) {
+            let shown = self
+                .client
+                .show_document(ShowDocumentParams {
+                    uri: uri.clone(),
+                    external: None,
+                    take_focus: Some(true),
+                    selection: range,
+                })
+                .await;
+            if let Ok(true) = shown {
+                return;
+            }
+        }
+
+        let path = match utils::file_path(&uri) {
+            Some(path) => path.display().to_string(),
+            None => uri.to_string(),
+        };
+        let location = match range {
+            Some(range) => format!("{}:{}", path, range.start.line + 1),
+            None => path,
+        };
+        self.client
+            .show_message(MessageType::INFO, format!("See {}.", location))
+            .await;
+    }
+
+    /// `vocab_links` links each entry under `Vocab` to its `accept.txt`.
+    fn vocab_links(&self, uri: &Url, src: &str) -> Vec<DocumentLink> {
+        let entries = ini::key_values(src, "Vocab");
+        if entries.is_empty() {
+            return vec![];
+        }
+
+        let (config_path, cwd) = self.config_for(uri);
+        let styles = match self.cli.config(config_path, cwd) {
+            Ok(config) => self.styles(config.styles_path),
+            Err(_) => return vec![],
+        };
+
+        entries
+            .into_iter()
+            .filter_map(|(range, name)| {
+                let accept = styles.vocab_dir(&name).join("accept.txt");
+                if !accept.is_file() {
+                    return None;
+                }
+                Some(DocumentLink {
+                    range,
+                    target: Url::from_file_path(accept).ok(),
+                    tooltip: None,
+                    data: None,
+                })
+            })
+            .collect()
+    }
+
+    /// `check_hover` summarizes the rule of the `Style.Rule = ...` override
+    /// under the cursor.
+    fn check_hover(&self, uri: &Url, src: &str, pos: Position) -> Option<Hover> {
+        let (check, range) = ini::key_at(src, pos)?;
+        ini::check_at(src, pos)?;
+
+        let value = match self.rule_file(uri, &check) {
+            Some(path) => yml::Rule::new(path.to_str()?).ok()?.summary(&check),
+            None if check.starts_with("Vale.")
+                && styles::VALE_RULES.contains(&&check["Vale.".len()..]) =>
+            {
+                format!("**{}**\n\nOne of Vale's built-in rules.", check)
+            }
+            None => return None,
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range),
+        })
+    }
+
+    /// `ignore_hover` explains the `BlockIgnores` or `TokenIgnores` pattern
+    /// under the cursor.
+    fn ignore_hover(&self, src: &str, pos: Position) -> Option<Hover> {
+        let (key, pattern) = ini::ignore_at(src, pos)?;
+        let link = regex101::link(&pattern).ok();
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: ini::ignore_info(&key, &pattern, link.as_deref()),
+            }),
+            range: None,
+        })
+    }
+
+    /// `package_hover` describes the entry under `Packages` at `pos` using the
+    /// package library, if we can reach it.
+    async fn package_hover(&self, src: &str, pos: Position) -> Option<Hover> {
+        let (range, name) = ini::key_values(src, "Packages")
+            .into_iter()
+            .find(|(r, _)| r.start <= pos && pos <= r.end)?;
+
+        let value = if Url::parse(&name).is_ok() {
+            format!("**{}**\n\nA package downloaded from this URL.", name)
+        } else {
+            let library = pkg::fetch().await.ok();
+            match library.and_then(|l| l.into_iter().find(|p| p.name == name)) {
+                Some(found) => format!(
+                    "**{}**\n\n{}\n\n[Homepage]({})",
+                    found.name, found.description, found.homepage
+                ),
+                None => format!(
+                    "**{}**\n\nNo details are available for this package (the package library couldn't be reached or doesn't list it).",
+                    name
+                ),
+            }
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range),
+        })
+    }
+
+    /// `package_links` links each entry under `Packages` to its homepage, or,
+    /// for packages given as URLs, to the URL itself.
+    async fn package_links(&self, src: &str) -> Vec<DocumentLink> {
+        let entries = ini::key_values(src, "Packages");
+        if entries.is_empty() {
+            return vec![];
+        }
+
+        let library = match pkg::fetch().await {
+            Ok(library) => library,
+            Err(err) => {
+                if !http::is_offline() {
+                    self.client
+                        .log_message(MessageType::ERROR, format!("Error: {}", err))
+                        .await;
+                }
+                vec![]
+            }
+        };
+
+        entries
+            .into_iter()
+            .filter_map(|(range, name)| {
+                if let Ok(url) = Url::parse(&name) {
+                    return Some(DocumentLink {
+                        range,
+                        target: Some(url),
+                        tooltip: None,
+                        data: None,
+                    });
+                }
+                let found = library.iter().find(|p| p.name == name)?;
+                Some(DocumentLink {
+                    range,
+                    target: Url::parse(&found.homepage).ok(),
+                    tooltip: Some(found.description.clone()),
+                    data: None,
+                })
+            })
+            .collect()
+    }
+
+    /// `config_definition` resolves a style under `BasedOnStyles` or a vocabulary
+    /// under `Vocab` to its files in the `StylesPath`.
+    fn config_definition(&self, uri: &Url, pos: Position) -> Option<GotoDefinitionResponse> {
+        let src = self.document_map.get(uri.as_str())?.to_string();
+        if let Some(check) = ini::check_at(&src, pos) {
+            return self
+                .rule_location(uri, &check)
+                .map(GotoDefinitionResponse::Scalar);
+        }
+
+        let (key, value) = ini::value_at(&src, pos)?;
+        let kind = match key.as_str() {
+            "BasedOnStyles" => styles::EntryType::Style,
+            "Vocab" => styles::EntryType::Vocab,
+            _ => return None,
+        };
+
+        let (config_path, cwd) = self.config_for(uri);
+        let config = self.cli.config(config_path, cwd).ok()?;
+        let files = self.styles(config.styles_path).files(&value, kind).ok()?;
+
+        let locations: Vec<Location> = files
+            .into_iter()
+            .filter_map(|f| Url::from_file_path(f).ok())
+            .map(|u| Location::new(u, Range::default()))
+            .collect();
+
+        if locations.is_empty() {
+            return None;
+        }
+        Some(GotoDefinitionResponse::Array(locations))
+    }
+
+    /// `rule_file` returns the YAML file defining `check` in the `StylesPath`
+    /// that applies to `uri`.
+    fn rule_file(&self, uri: &Url, check: &str) -> Option<PathBuf> {
+        let (config_path, cwd) = self.config_for(uri);
+        let config = self.cli.config(config_path, cwd).ok()?;
+        self.styles(config.styles_path).rule_path(check)
+    }
+
+    /// `rule_lenses` places a "Run against open files" lens at the top of a
+    /// rule, and "Compile" and "Open on Regex101" lenses above the pattern keys
+    /// of a rule that can be compiled.
+    fn rule_lenses(&self, uri: &Url) -> Option<Vec<CodeLens>> {
+        let rule = yml::Rule::new(uri.to_file_path().ok()?.to_str()?).ok()?;
+        let rope = self.document_map.get(uri.as_str())?;
+        let arg = Value::String(uri.to_string());
+
+        let mut lenses = vec![CodeLens {
+            range: Range::default(),
+            command: Some(Command {
+                title: "Run against open files".to_string(),
+                command: "cli.runRule".to_string(),
+                arguments: Some(vec![arg.clone()]),
+            }),
+            data: None,
+        }];
+        if !rule.can_compile() {
+            return Some(lenses);
+        }
+
+        for (i, line) in rope.lines().enumerate() {
+            let line = line.to_string();
+            if !(line.starts_with("tokens:") || line.starts_with("swap:")) {
+                continue;
+            }
+            let range = Range::new(Position::new(i as u32, 0), Position::new(i as u32, 0));
+            lenses.push(CodeLens {
+                range,
+                command: Some(Command {
+                    title: "Compile".to_string(),
+                    command: "cli.compile".to_string(),
+                    arguments: Some(vec![arg.clone(), Value::Bool(false)]),
+                }),
+                data: None,
+            });
+            if http::is_offline() {
+                continue;
+            }
+            lenses.push(CodeLens {
+                range,
+                command: Some(Command {
+                    title: "Open on Regex101".to_string(),
+                    command: "cli.compile".to_string(),
+                    arguments: Some(vec![arg.clone()]),
+                }),
+                data: None,
+            });
+        }
+
+        Some(lenses)
+    }
+
+    /// `safe_fixes` returns the edits for the alerts whose fix is unambiguous:
+    /// those that remove their match or have a single replacement.
+    fn safe_fixes(&self, uri: &Url, diagnostics: &[Diagnostic]) -> Vec<TextEdit> {
+        self.unambiguous_fixes(uri, diagnostics, |alert, _| {
+            match alert.action.name.as_deref() {
+                Some("remove") => vec!["".to_string()],
+                Some("replace") => alert.action.params.clone().unwrap_or_default(),
+                _ => vec![],
+            }
+        })
+    }
+
+    /// `unambiguous_fixes` returns the edits for the alerts that `suggest`
+    /// (given each alert and its raw data) finds exactly one fix for, leaving
+    /// the choice between several to the user.
+    fn unambiguous_fixes(
+        &self,
+        uri: &Url,
+        diagnostics: &[Diagnostic],
+        mut suggest: impl FnMut(&vale::ValeAlert, &Value) -> Vec<String>,
+    ) -> Vec<TextEdit> {
+        // NOTE: `suggest` may run Vale, so we don't hold on to the map entry
+        // (which would block changes to the document) in the meantime.
+        let rope = self.document_map.get(uri.as_str()).map(|rope| rope.clone());
+        let encoding = self.position_encoding();
+
+        let edits = diagnostics
+            .iter()
+            .filter_map(|d| {
+                let data = d.data.as_ref()?;
+                let alert: vale::ValeAlert = serde_json::from_value(data.clone()).ok()?;
+                let mut fixes = suggest(&alert, data);
+                if fixes.len() != 1 {
+                    return None;
+                }
+                let fix = fixes.remove(0);
+                Some(utils::alert_to_edit(&alert, fix, rope.as_ref(), &encoding))
+            })
+            .collect();
+
+        non_overlapping(edits)
+    }
+
+    /// `apply_fixes` asks the client to apply the safe fixes for the latest
+    /// diagnostics of `uri`, and re-lints the result.
+    async fn apply_fixes(&self, uri: &Url) {
+        let diagnostics = match self.diagnostic_map.get(uri.as_str()) {
+            Some(diagnostics) => diagnostics.clone(),
+            None => return,
+        };
+        let edits = self.safe_fixes(uri, &diagnostics);
+        if edits.is_empty() {
+            return;
+        }
+
+        let edit = WorkspaceEdit {
+            changes: Some([(uri.clone(), edits)].into_iter().collect()),
+            ..WorkspaceEdit::default()
+        };
+        match self.client.apply_edit(edit).await {
+            Ok(response) if response.applied => self.relint(uri).await,
+            Ok(response) => {
+                self.client
+                    .log_message(
+                        MessageType::WARNING,
+                        format!(
+                            "Fixes weren't applied: {}",
+                            response.failure_reason.unwrap_or_default()
+                        ),
+                    )
+                    .await;
+            }
+            Err(err) => {
+                self.client
+                    .log_message(MessageType::ERROR, format!("Error: {}", err))
+                    .await;
+            }
+        }
+    }
+
+    /// `fix_all` lints the document and combines the fixes of every alert
+    /// with a single suggestion into a `source.fixAll` action.
+    async fn fix_all(&self, uri: Url) -> Option<CodeActionResponse> {
+        if !self.cli.is_installed() {
+            return None;
+        }
+
+        let text = self.document_map.get(uri.as_str()).map(|r| r.to_string());
+        let diagnostics = match self.queued(&uri, || self.lint(&uri, text.as_deref())).await {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => {
+                self.client
+                    .log_message(MessageType::ERROR, format!("Error: {}", err))
+                    .await;
+                return None;
+            }
+        };
+
+        let mut errors = Vec::new();
+        let applied = self.unambiguous_fixes(&uri, &diagnostics, |alert, data| {
+            if alert.action.name.as_deref().unwrap_or("").is_empty() {
+                return vec![];
+            }
+            match self.cli.fix(&data.to_string()) {
+                Ok(fixed) => fixed.suggestions,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    vec![]
+                }
+            }
+        });
+        for e in errors {
+            self.client
+                .log_message(MessageType::ERROR, format!("Error: {}", e))
+                .await;
+        }
+
+        if applied.is_empty() {
+            return None;
+        }
+
+        Some(vec![CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Fix all Vale alerts".to_string(),
+            kind: Some(CodeActionKind::SOURCE_FIX_ALL),
+            diagnostics: Some(diagnostics),
+            edit: Some(WorkspaceEdit {
+                changes: Some([(uri, applied)].into_iter().collect()),
+                ..WorkspaceEdit::default()
+            }),
+            ..CodeAction::default()
+        })])
+    }
+
+    /// `lint` runs Vale on the given document and converts its alerts into
+    /// diagnostics.
+    ///
+    /// If `text` is provided, it's linted in place of the file on disk.
+    fn lint(&self, uri: &Url, text: Option<&str>) -> std::result::Result<Vec<Diagnostic>, Error> {
+        let fp = self
+            .lint_path(uri)
+            .ok_or_else(|| Error::from("No file path found. Is the file saved?"))?;
+
+        let generation = self.generation(uri);
+        let config_path = self.config_for(uri).0;
+        let key = match text {
+            Some(text) => Some(text.to_string()),
+            None => std::fs::read_to_string(&fp).ok(),
+        }
+        .map(|text| self.lint_key(&text, &config_path));
+        if let Some(diagnostics) = key.and_then(|key| self.cached_lint(uri, key)) {
+            return Ok(diagnostics);
+        }
+
+        let start = Instant::now();
+        let result = match text {
+            Some(text) => {
+                self.cli
+                    .run_stdin(fp, text, config_path.clone(), self.config_filter())?
+            }
+            None => self
+                .cli
+                .run(fp, config_path.clone(), self.config_filter())?,
+        };
+        if let Ok(mut status) = self.status.write() {
+            status.last_lint_duration = Some(start.elapsed().as_millis() as u64);
+            status.config_path = Some(config_path).filter(|c| !c.is_empty());
+        }
+        if self.generation(uri) != generation {
+            return Err(Error::Stale);
+        }
+
+        let encoding = self.position_encoding();
+        let severities = self.get_setting("severities");
+        let rope = self.document_map.get(uri.as_str());
+        let mut diagnostics = Vec::new();
+        for (_, v) in result.iter() {
+            for alert in v {
+                diagnostics.push(utils::alert_to_diagnostic(
+                    alert,
+                    rope.as_deref(),
+                    &encoding,
+                    severities.as_ref(),
+                ));
+            }
+        }
+        self.link_rules(uri, &mut diagnostics);
+
+        if let Some(rope) = rope {
+            self.linted_map.insert(uri.to_string(), rope.clone());
+        }
+        if let Some((hash, modified)) = key {
+            self.lint_cache.insert(
+                uri.to_string(),
+                CachedLint {
+                    hash,
+                    modified,
+                    diagnostics: diagnostics.clone(),
+                },
+            );
+        }
+        self.diagnostic_map
+            .insert(uri.to_string(), diagnostics.clone());
+        Ok(diagnostics)
+    }
+
+    /// `queued` runs `f`, a lint of `uri`, once fewer than `maxConcurrentLints`
+    /// others are running. Lints start in the order they were queued, and one
+    /// for a document that changed while it waited is skipped.
+    async fn queued<T>(
+        &self,
+        uri: &Url,
+        f: impl FnOnce() -> std::result::Result<T, Error>,
+    ) -> std::result::Result<T, Error> {
+        let generation = self.generation(uri);
+        let _permit = self
+            .lint_slots()
+            .acquire_owned()
+            .await
+            .map_err(|e| Error::from(e.to_string()))?;
+        if self.generation(uri) != generation {
+            return Err(Error::Stale);
+        }
+        f()
+    }
+
+    /// `lint_slots` returns the semaphore bounding concurrent Vale runs,
+    /// replacing it if `maxConcurrentLints` has changed.
+    fn lint_slots(&self) -> Arc<Semaphore> {
+        let size = self
+            .get_setting("maxConcurrentLints")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(4)
+            .max(1) as usize;
+
+        if let Ok(slots) = self.lint_slots.read() {
+            if let Some((n, semaphore)) = slots.as_ref() {
+                if *n == size {
+                    return semaphore.clone();
+                }
+            }
+        }
+
+        let semaphore = Arc::new(Semaphore::new(size));
+        if let Ok(mut slots) = self.lint_slots.write() {
+            *slots = Some((size, semaphore.clone()));
+        }
+        semaphore
+    }
+
+    /// `lint_key` identifies a lint of `text` with `config_path`: a hash of
+    /// everything that goes into it, and when the config was last modified.
+    fn lint_key(&self, text: &str, config_path: &str) -> (u64, Option<SystemTime>) {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        config_path.hash(&mut hasher);
+        self.config_filter().hash(&mut hasher);
+
+        let modified = std::fs::metadata(config_path)
+            .and_then(|m| m.modified())
+            .ok();
+        (hasher.finish(), modified)
+    }
+
+    /// `cached_lint` returns the diagnostics of an earlier lint of `uri` with
+    /// the same `key`, if there was one.
+    fn cached_lint(&self, uri: &Url, key: (u64, Option<SystemTime>)) -> Option<Vec<Diagnostic>> {
+        let cached = self.lint_cache.get(uri.as_str())?;
+        if (cached.hash, cached.modified) != key {
+            return None;
+        }
+
+        let diagnostics = cached.diagnostics.clone();
+        if let Some(rope) = self.document_map.get(uri.as_str()) {
+            self.linted_map.insert(uri.to_string(), rope.clone());
+        }
+        self.diagnostic_map
+            .insert(uri.to_string(), diagnostics.clone());
+        Some(diagnostics)
+    }
+
+    /// `lint_region` re-lints only the sections of `text` that differ from the
+    /// last version we linted, and merges the results with the diagnostics we
+    /// already have for the rest of the document.
+    fn lint_region(&self, uri: &Url, text: &str) -> std::result::Result<Vec<Diagnostic>, Error> {
+        let (old, cached) = match (
+            self.linted_map.get(uri.as_str()).map(|r| r.to_string()),
+            self.diagnostic_map.get(uri.as_str()).map(|d| d.clone()),
+        ) {
+            (Some(old), Some(cached)) => (old, cached),
+            _ => return self.lint(uri, Some(text)),
+        };
+
+        let language = match self.language_map.get(uri.as_str()) {
+            Some(language) => language.clone(),
+            None => utils::language_id(uri.path()).to_string(),
+        };
+        let (start, end, delta) =
+            match utils::changed_region(&old, text, &utils::headings(text, &language)) {
+                Some(region) => region,
+                None => return Ok(cached),
+            };
+        // There's nothing to gain when most of the document has changed.
+        if (end - start) * 2 > text.lines().count() {
+            return self.lint(uri, Some(text));
+        }
+
+        let fp = self
+            .lint_path(uri)
+            .ok_or_else(|| Error::from("No file path found. Is the file saved?"))?;
+        let mut region: String = text
+            .lines()
+            .skip(start)
+            .take(end - start)
+            .collect::<Vec<_>>()
+            .join("\n");
+        region.push('\n');
+
+        let generation = self.generation(uri);
+        let config_path = self.config_for(uri).0;
+        let started = Instant::now();
+        let result = self
+            .cli
+            .run_stdin(fp, &region, config_path.clone(), self.config_filter())?;
+        if let Ok(mut status) = self.status.write() {
+            status.last_lint_duration = Some(started.elapsed().as_millis() as u64);
+            status.config_path = Some(config_path).filter(|c| !c.is_empty());
+        }
+        if self.generation(uri) != generation {
+            return Err(Error::Stale);
+        }
+
+        let encoding = self.position_encoding();
+        let severities = self.get_setting("severities");
+        let rope = self.document_map.get(uri.as_str());
+        let mut found = Vec::new();
+        for (_, v) in result.iter() {
+            for alert in v {
+                let mut alert = alert.clone();
+                alert.line += start;
+                found.push(utils::alert_to_diagnostic(
+                    &alert,
+                    rope.as_deref(),
+                    &encoding,
+                    severities.as_ref(),
+                ));
+            }
+        }
+        self.link_rules(uri, &mut found);
+
+        let old_end = (end as isize - delta) as usize;
+        let mut diagnostics = utils::shift_diagnostics(cached, start, old_end, delta);
+        diagnostics.extend(found);
+        diagnostics.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+
+        if let Some(rope) = rope {
+            self.linted_map.insert(uri.to_string(), rope.clone());
+        }
+        self.diagnostic_map
+            .insert(uri.to_string(), diagnostics.clone());
+        Ok(diagnostics)
+    }
+
+    async fn publish(
+        &self,
+        uri: Url,
+        version: Option<i32>,
+        result: std::result::Result<Vec<Diagnostic>, Error>,
+    ) {
+        self.send_status().await;
+        match result {
+            Ok(diagnostics) => {
+                let changed = self.pull_diagnostics()
+                    || self.send_diagnostics(uri, diagnostics, version).await;
+                // The per-section counts depend on the latest diagnostics.
+                if changed && self.client_cap(|c| c.inlay_hint_refresh) {
+                    let _ = self.client.inlay_hint_refresh().await;
+                }
+            }
+            // A newer version of the document is (or will be) linted instead.
+            Err(Error::Stale) => {}
+            Err(err) => {
+                self.client
+                    .log_message(MessageType::ERROR, format!("Parsing error: {:?}", err))
+                    .await;
+                match serde_json::from_str::<vale::ValeError>(&err.to_string()) {
+                    Ok(parsed) => {
+                        self.client.show_message(MessageType::ERROR, parsed).await;
+                    }
+                    Err(e) => {
+                        self.client.show_message(MessageType::ERROR, e).await;
+                    }
+                };
+            }
+        }
+    }
+
+    /// `send_diagnostics` publishes `diagnostics` for `uri`, stamped with the
+    /// `version` of the document they were computed for, unless they're the
+    /// same as the last ones we published (to avoid flicker in the client) or
+    /// the document has changed since. It reports whether anything was sent.
+    async fn send_diagnostics(
+        &self,
+        uri: Url,
+        diagnostics: Vec<Diagnostic>,
+        version: Option<i32>,
+    ) -> bool {
+        let stale = version
+            .zip(self.version(&uri))
+            .is_some_and(|(version, current)| version < current);
+        if stale
+            || self
+                .published_map
+                .get(uri.as_str())
+                .is_some_and(|last| *last == diagnostics)
+        {
+            return false;
+        }
+        self.published_map
+            .insert(uri.to_string(), diagnostics.clone());
+
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+        true
+    }
+
+    /// `begin_progress` starts a work done progress report, if the client
+    /// supports them.
+    async fn begin_progress(&self, title: &str) -> Option<ProgressToken> {
+        if !self.client_cap(|c| c.progress) {
+            return None;
+        }
+
+        let id = PROGRESS_ID.fetch_add(1, Ordering::Relaxed);
+        let token = ProgressToken::String(format!("vale-ls/{}", id));
+        self.client
+            .send_request::<request::WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: token.clone(),
+            })
+            .await
+            .ok()?;
+
+        self.client
+            .send_notification::<notification::Progress>(ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(WorkDoneProgress::Begin(
+                    WorkDoneProgressBegin {
+                        title: title.to_string(),
+                        ..WorkDoneProgressBegin::default()
+                    },
+                )),
+            })
+            .await;
+        Some(token)
+    }
+
+    /// `end_progress` finishes a report started by `begin_progress`.
+    async fn end_progress(&self, token: Option<ProgressToken>) {
+        if let Some(token) = token {
+            self.client
+                .send_notification::<notification::Progress>(ProgressParams {
+                    token,
+                    value: ProgressParamsValue::WorkDone(WorkDoneProgress::End(
+                        WorkDoneProgressEnd::default(),
+                    )),
+                })
+                .await;
+        }
+    }
+
+    /// `send_status` notifies the client of the server's current state.
+    async fn send_status(&self) {
+        let known = match self.status.read() {
+            Ok(status) => status.version.is_some(),
+            Err(_) => return,
+        };
+        // NOTE: Checking the version runs Vale, so we only do it until it
+        // succeeds (or `valePath` changes), and never while holding the lock.
+        // If it fails (e.g., a wrapper script with other output), we report
+        // the version as unknown.
+        let version = if known {
+            None
+        } else {
+            self.cli.version(false).ok()
+        };
+
+        let status = match self.status.write() {
+            Ok(mut status) => {
+                if status.version.is_none() {
+                    status.version = version;
+                }
+                status.clone()
+            }
+            Err(_) => return,
+        };
+        self.client
+            .send_notification::<StatusNotification>(status)
+            .await;
+    }
+
+    /// `reset_version` forgets the Vale version we report, after the binary
+    /// may have changed.
+    fn reset_version(&self) {
+        if let Ok(mut status) = self.status.write() {
+            status.version = None;
+        }
+    }
+
+    async fn set_syncing(&self, syncing: bool) {
+        if let Ok(mut status) = self.status.write() {
+            status.syncing = syncing;
+        }
+        self.send_status().await;
+    }
+
+    /// `schedule_lint` lints a changed document after `lintDelay` milliseconds,
+    /// unless another change to it arrives in the meantime.
+    ///
+    /// The wait happens in its own task so that `didChange` returns right
+    /// away rather than holding up the requests behind it.
+    fn schedule_lint(&self, item: TextDocumentItem) {
+        let backend = self.clone();
+        let delay = Duration::from_millis(self.lint_delay());
+        let uri = item.uri.to_string();
+
+        let pending = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            // NOTE: Once the delay is over, a later change no longer cancels
+            // the lint (only its results, which are stale by then).
+            tokio::spawn(async move {
+                if item.text.lines().count() >= backend.region_threshold() {
+                    backend.on_region_edit(item).await;
+                } else {
+                    backend.on_edit(item).await;
+                }
+            });
+        });
+
+        if let Some(previous) = self.debounce_map.insert(uri, pending.abort_handle()) {
+            previous.abort();
+        }
+    }
+
+    async fn init(&self, params: Option<Value>, cwd: String) {
+        self.parse_params(params).await;
+        self.apply_cli().await;
+        self.apply_network().await;
+        self.apply_logging().await;
+    }
+
+    /// `install` installs or updates the managed Vale binary. It's spawned from
+    /// `initialized`, since a download can take a while, and lints the open
+    /// documents once Vale is available.
+    async fn install(&self) {
+        if !self.should_install() {
+            return;
+        } else if http::is_offline() {
+            self.client
+                .log_message(
+                    MessageType::INFO,
+                    "Offline mode is enabled; skipping the Vale installation check.",
+                )
+                .await;
+            return;
+        }
+
+        let was_installed = self.cli.is_installed();
+        let token = self.begin_progress("Checking for Vale updates").await;
+        let result = self.cli.install_or_update().await;
+        self.end_progress(token).await;
+
+        match result {
+            Ok(status) => {
+                self.client.log_message(MessageType::INFO, status).await;
+                self.reset_version();
+                self.send_status().await;
+                if !was_installed {
+                    self.relint_all().await;
+                }
+            }
+            Err(err) => {
+                self.client
+                    .show_message(MessageType::INFO, err.to_string())
+                    .await;
+                self.client
+                    .log_message(MessageType::ERROR, err.to_string())
+                    .await;
+            }
+        }
+    }
+
+    /// `apply_cli` uses the executable given by `valePath`, if any, to
+    /// run Vale, passing it any `valeArgs`.
+    async fn apply_cli(&self) {
+        if let Err(err) = self.cli.set_custom_exe(&self.get_string("valePath")) {
+            self.client
+                .show_message(MessageType::ERROR, format!("Invalid valePath: {}", err))
+                .await;
+        }
+
+        let args = match self.get_setting("valeArgs") {
+            Some(Value::Array(args)) => args
+                .iter()
+                .filter_map(|a| a.as_str())
+                .map(str::to_string)
+                .collect(),
+            _ => vec![],
+        };
+        self.cli.set_extra_args(args);
+        self.cli.set_binary_dir(&self.get_string("binaryDir"));
+        self.cli.set_arch(&self.get_string("arch"));
+        if let Err(err) = regex101::set_flavor(&self.get_string("regex101Flavor")) {
+            self.client
+                .show_message(
+                    MessageType::ERROR,
+                    format!("Invalid regex101Flavor: {}", err),
+                )
+                .await;
+        }
+
+        match self.get_string("updateChannel").as_str() {
+            "" | "stable" => self.cli.set_prerelease(false),
+            "prerelease" => self.cli.set_prerelease(true),
+            other => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!(
+                            "Invalid updateChannel '{}'; expected 'stable' or 'prerelease'.",
+                            other
+                        ),
+                    )
+                    .await;
+            }
+        }
+
+        self.reset_version();
+    }
+
+    /// `apply_network` routes our requests through the `proxy` option, if set,
+    /// or disables them entirely in `offline` mode. It also sets how long the
+    /// package library is cached (`packageCacheTTL`, in seconds), how long we
+    /// wait for the network (`networkTimeout`, in seconds), and how many times
+    /// we retry a failed download (`downloadRetries`).
+    async fn apply_network(&self) {
+        http::set_offline(self.get_setting("offline") == Some(Value::Bool(true)));
+        if let Some(ttl) = self.get_setting("packageCacheTTL").and_then(|v| v.as_u64()) {
+            pkg::set_ttl(ttl);
+        }
+        if let Some(secs) = self.get_setting("networkTimeout").and_then(|v| v.as_u64()) {
+            http::set_timeout(secs);
+        }
+        if let Some(n) = self.get_setting("downloadRetries").and_then(|v| v.as_u64()) {
+            http::set_retries(n as u32);
+        }
+        if let Err(err) = http::set_proxy(&self.get_string("proxy")) {
+            self.client
+                .show_message(MessageType::ERROR, format!("Invalid proxy: {}", err))
+                .await;
+        }
+    }
+
+    /// `apply_logging` writes our logs to `logFile` (relative to the workspace
+    /// root), if set, at `logLevel` and above.
+    async fn apply_logging(&self) {
+        let path = match self.get_string("logFile") {
+            path if path.is_empty() => path,
+            path => Path::new(&self.root_path())
+                .join(path)
+                .display()
+                .to_string(),
+        };
+        if let Err(err) = logging::set_file(&path, &self.get_string("logLevel")) {
+            self.client
+                .show_message(MessageType::ERROR, format!("Invalid logFile: {}", err))
+                .await;
+        }
+    }
+
+    /// `should_lint` reports whether the document is one we lint: an enabled
+    /// file type that isn't ignored.
+    fn should_lint(&self, uri: &Url) -> bool {
+        if !self.is_enabled(uri) || self.is_too_large(uri) {
+            return false;
+        }
+
+        let fp = match uri.to_file_path() {
+            Ok(fp) => fp,
+            Err(_) => return true,
+        };
+        let exclude: Vec<String> = match self.get_setting("exclude") {
+            Some(Value::Array(globs)) => globs
+                .iter()
+                .filter_map(|g| g.as_str())
+                .map(str::to_string)
+                .collect(),
+            _ => vec![],
+        };
+        !self
+            .ignores
+            .is_ignored(Path::new(&self.root_for(&fp)), &fp, &exclude)
+    }
+
+    /// `is_too_large` reports whether the document is bigger than
+    /// `maxFileSize` bytes (5 MiB by default; 0 means there's no limit).
+    fn is_too_large(&self, uri: &Url) -> bool {
+        let max = self
+            .get_setting("maxFileSize")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(5 * 1024 * 1024);
+        if max == 0 {
+            return false;
+        }
+
+        let size = match self.document_map.get(uri.as_str()) {
+            Some(rope) => Some(rope.len_bytes() as u64),
+            None => uri
+                .to_file_path()
+                .ok()
+                .and_then(|fp| std::fs::metadata(fp).ok())
+                .map(|m| m.len()),
+        };
+        size.is_some_and(|size| size > max)
+    }
+
+    /// `is_enabled` reports whether the document's language ID (or, for
+    /// documents the client hasn't opened, its extension) is listed in
+    /// `enabledFileTypes`.
+    fn is_enabled(&self, uri: &Url) -> bool {
+        let enabled: Vec<String> = match self.get_setting("enabledFileTypes") {
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(|t| t.as_str())
+                .map(str::to_string)
+                .collect(),
+            _ => utils::DEFAULT_FILE_TYPES
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+        };
+
+        let ext = uri.path().rsplit_once('.').map_or("", |(_, ext)| ext);
+        let language = match self.language_map.get(uri.as_str()) {
+            Some(language) => language.clone(),
+            None => utils::language_id(uri.path()).to_string(),
+        };
+        enabled.iter().any(|t| *t == language || *t == ext)
+    }
+
+    fn resolve_code_actions(&self) -> bool {
+        self.client_cap(|c| c.code_action_resolve)
+    }
+
+    fn should_install(&self) -> bool {
+        self.get_setting("installVale") == Some(Value::Bool(true))
+    }
+
+    fn config_path(&self) -> String {
+        match self.get_string("configPath") {
+            path if path.is_empty() => path,
+            path => utils::normalize_path(Path::new(&path))
+                .display()
+                .to_string(),
+        }
+    }
+
+    fn config_filter(&self) -> String {
+        self.get_string("filter")
+    }
+
+    fn pull_diagnostics(&self) -> bool {
+        self.client_cap(|c| c.pull_diagnostics)
+    }
+
+    fn should_lint_on_change(&self) -> bool {
+        self.run_mode() == RunMode::OnType
+    }
+
+    /// `run_mode` returns the `runMode` setting, falling back to the older
+    /// `lintOnChange` flag when it isn't set.
+    fn run_mode(&self) -> RunMode {
+        match self.get_string("runMode").as_str() {
+            "onType" => RunMode::OnType,
+            "manual" => RunMode::Manual,
+            "onSave" => RunMode::OnSave,
+            _ if self.get_setting("lintOnChange") == Some(Value::Bool(true)) => RunMode::OnType,
+            _ => RunMode::OnSave,
+        }
+    }
+
+    /// `region_threshold` is the number of lines at which we start linting
+    /// only the changed sections of a document as it's edited.
+    fn region_threshold(&self) -> usize {
+        self.get_setting("regionLintThreshold")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1000) as usize
+    }
+
+    fn lint_delay(&self) -> u64 {
+        self.get_setting("lintDelay")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(500)
+    }
+
+    fn should_apply_fixes_on_save(&self) -> bool {
+        self.get_setting("applyFixesOnSave") == Some(Value::Bool(true))
+    }
+
+    fn should_fix_on_will_save(&self) -> bool {
+        self.get_setting("fixOnWillSave") == Some(Value::Bool(true))
+    }
+
+    /// `should_clear_on_close` reports whether to clear a document's
+    /// diagnostics when it's closed (the default), rather than leave them in
+    /// the client's list of problems.
+    fn should_clear_on_close(&self) -> bool {
+        self.get_setting("clearDiagnosticsOnClose") != Some(Value::Bool(false))
+    }
+
+    fn should_scan_workspace(&self) -> bool {
+        self.get_setting("scanWorkspaceOnStartup") == Some(Value::Bool(true))
+            && self.run_mode() != RunMode::Manual
+    }
+
+    fn should_sync(&self) -> bool {
+        self.get_setting("syncOnStartup") == Some(Value::Bool(true))
+    }
+
+    fn should_sync_on_change(&self) -> bool {
+        self.get_setting("syncOnChange") == Some(Value::Bool(true))
+    }
+
+    /// `lint_path` returns the path Vale should lint the document at `uri` as.
+    ///
+    /// Documents that aren't on disk, such as untitled ones or those in a
+    /// virtual workspace (e.g., `vscode-vfs:`), are linted through stdin as if
+    /// they were a file of the same name in the workspace root. Those without
+    /// an extension get one that matches their language.
+    fn lint_path(&self, uri: &Url) -> Option<PathBuf> {
+        if let Some(fp) = utils::file_path(uri) {
+            return Some(fp);
+        }
+
+        let name = uri
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .filter(|n| {
+                n.rsplit_once('.')
+                    .is_some_and(|(s, e)| !s.is_empty() && !e.is_empty())
+            });
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => {
+                let language = self.language_map.get(uri.as_str())?.clone();
+                format!("untitled.{}", utils::language_ext(&language)?)
+            }
+        };
+
+        let root = match self.root_path() {
+            root if root.is_empty() => std::env::current_dir().ok()?,
+            root => PathBuf::from(root),
+        };
+        Some(root.join(name))
+    }
+
+    fn root_path(&self) -> String {
+        self.get_string("root")
+    }
+
+    /// `position_encoding` returns the encoding negotiated with the client
+    /// during `initialize`.
+    fn position_encoding(&self) -> PositionEncodingKind {
+        match self.client_caps.get() {
+            Some(caps) => caps.position_encoding.clone(),
+            None => PositionEncodingKind::UTF16,
+        }
+    }
+
+    /// `client_cap` reports whether the client has the capability `cap`
+    /// selects (none, before `initialize`).
+    fn client_cap(&self, cap: impl Fn(&ClientCaps) -> bool) -> bool {
+        self.client_caps.get().is_some_and(cap)
+    }
+
+    /// `config_file` returns the config file Vale uses for the given document:
+    /// the one `configPaths` maps its folder to, `configPath`, or the one Vale
+    /// itself would discover.
+    fn config_file(&self, fp: &Path) -> Option<PathBuf> {
+        if let Some(config) = self.mapped_config(fp) {
+            return Some(config);
+        }
+        let config_path = self.config_path();
+        if !config_path.is_empty() {
+            return Some(PathBuf::from(config_path));
+        }
+        ini::discover(fp.parent()?)
+    }
+
+    /// `config_paths` returns the folders and config files of `configPaths`,
+    /// with relative paths resolved against the workspace root.
+    fn config_paths(&self) -> Vec<(PathBuf, PathBuf)> {
+        let root = PathBuf::from(self.root_path());
+        let resolve = |p: &str| utils::normalize_path(&root.join(p));
+        match self.get_setting("configPaths") {
+            Some(Value::Object(paths)) => paths
+                .iter()
+                .filter_map(|(folder, config)| Some((resolve(folder), resolve(config.as_str()?))))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// `mapped_config` returns the config file `configPaths` maps the folder
+    /// containing `fp` to, choosing the most specific folder if several match.
+    fn mapped_config(&self, fp: &Path) -> Option<PathBuf> {
+        self.mapping(fp).map(|(_, config)| config)
+    }
+
+    /// `mapping` returns the `configPaths` entry (folder and config file) that
+    /// applies to `fp`, if any.
+    fn mapping(&self, fp: &Path) -> Option<(PathBuf, PathBuf)> {
+        self.config_paths()
+            .into_iter()
+            .filter(|(folder, _)| fp.starts_with(folder))
+            .max_by_key(|(folder, _)| folder.components().count())
+    }
+
+    /// `styles` returns the (cached) index of the `StylesPath` at `root`.
+    fn styles(&self, root: PathBuf) -> Arc<styles::StylesPath> {
+        self.styles_map
+            .entry(root.clone())
+            .or_insert_with(|| Arc::new(styles::StylesPath::new(root)))
+            .clone()
+    }
+
+    /// `invalidate` drops what we've cached about configs, `StylesPath`s, and
+    /// ignore files, and the lint results that depend on them, after
+    /// something on disk may have changed them.
+    fn invalidate(&self) {
+        self.cli.invalidate();
+        self.styles_map.clear();
+        self.ignores.clear();
+        self.lint_cache.clear();
+    }
+
+    /// `config_for` returns the config file and working directory to run Vale
+    /// with for the given document.
+    fn config_for(&self, uri: &Url) -> (String, String) {
+        match utils::file_path(uri) {
+            Some(fp) => (
+                self.config_file(&fp)
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+                self.root_for(&fp),
+            ),
+            None => (self.config_path(), self.root_path()),
+        }
+    }
+
+    /// `root_for` returns the innermost workspace folder containing `fp`,
+    /// falling back to the root the server was started with.
+    fn root_for(&self, fp: &Path) -> String {
+        self.workspace_roots()
+            .into_iter()
+            .filter(|root| fp.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map(|root| root.display().to_string())
+            .unwrap_or_else(|| self.root_path())
+    }
+
+    /// `workspace_roots` returns the client's workspace folders or, if it
+    /// didn't send any, the root the server was started with.
+    fn workspace_roots(&self) -> Vec<PathBuf> {
+        let folders: Vec<PathBuf> = match self.get_setting("workspaceFolders") {
+            Some(Value::Array(folders)) => folders
+                .iter()
+                .filter_map(|f| f.as_str())
+                .map(PathBuf::from)
+                .collect(),
+            _ => vec![],
+        };
+        if !folders.is_empty() {
+            return folders;
+        }
+
+        let root = self.root_path();
+        if root.is_empty() {
+            return vec![];
+        }
+        vec![PathBuf::from(root)]
+    }
+
+    /// `workspace_configs` returns the contents of every config file in the
+    /// workspace, along with those set by `configPath` and `configPaths`.
+    fn workspace_configs(&self) -> Vec<(Url, String)> {
+        let mut configs: Vec<PathBuf> = self
+            .workspace_roots()
+            .iter()
+            .flat_map(|root| ini::find_all(root))
+            .collect();
+
+        let config_path = self.config_path();
+        if !config_path.is_empty() {
+            configs.push(PathBuf::from(config_path));
+        }
+        configs.extend(self.config_paths().into_iter().map(|(_, config)| config));
+        configs.sort();
+        configs.dedup();
+
+        configs
+            .into_iter()
+            .filter_map(|path| {
+                let uri = Url::from_file_path(&path).ok()?;
+                let src = match self.document_map.get(uri.as_str()) {
+                    Some(rope) => rope.to_string(),
+                    None => std::fs::read_to_string(&path).ok()?,
+                };
+                Some((uri, src))
+            })
+            .collect()
+    }
+
+    /// `renamed_check` returns the old and new names of the check defined by a
+    /// rule file that's being moved from `old` to `new`, if both are rules.
+    fn renamed_check(&self, old: &str, new: &str) -> Option<(String, String)> {
+        let old = Url::parse(old).ok()?;
+        let new = Url::parse(new).ok()?.to_file_path().ok()?;
+
+        let (config_path, cwd) = self.config_for(&old);
+        let config = self.cli.config(config_path, cwd).ok()?;
+        let styles = self.styles(config.styles_path);
+
+        let old = old.to_file_path().ok()?;
+        if !styles.is_rule(old.to_str()?) || !styles.is_rule(new.to_str()?) {
+            return None;
+        }
+
+        let (old, new) = (check_name(&old)?, check_name(&new)?);
+        if old == new {
+            return None;
+        }
+        Some((old, new))
+    }
+
+    /// `watch_files` asks the client to tell us about changes to config files
+    /// and to anything in the `StylesPath`, since either can change the
+    /// results of every open document.
+    async fn watch_files(&self) {
+        if !self.client_cap(|c| c.watch_files) {
+            return;
+        }
+
+        let mut watchers = vec![
+            FileSystemWatcher {
+                glob_pattern: GlobPattern::String(format!(
+                    "**/{{{}}}",
+                    ini::CONFIG_NAMES.join(",")
+                )),
+                kind: None,
+            },
+            FileSystemWatcher {
+                glob_pattern: GlobPattern::String("**/{.gitignore,.valeignore}".to_string()),
+                kind: None,
+            },
+        ];
+        if self.cli.is_installed() {
+            let config = self.cli.config(self.config_path(), self.root_path());
+            // NOTE: The pattern is relative to the `StylesPath` (rather than
+            // a glob of its path) so that Windows separators and any glob
+            // characters in the path don't matter.
+            if let Some(base_uri) = config
+                .ok()
+                .and_then(|c| Url::from_directory_path(c.styles_path).ok())
+            {
+                watchers.push(FileSystemWatcher {
+                    glob_pattern: GlobPattern::Relative(RelativePattern {
+                        base_uri: OneOf::Right(base_uri),
+                        pattern: "**/*.{yml,txt,tengo}".to_string(),
+                    }),
+                    kind: None,
+                });
+            }
+        }
+
+        let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+        let registration = Registration {
+            id: "vale-ls-watcher".to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: serde_json::to_value(options).ok(),
+        };
+        if let Err(err) = self.client.register_capability(vec![registration]).await {
+            self.client
+                .log_message(
+                    MessageType::ERROR,
+                    format!("Failed to watch files: {}", err),
+                )
+                .await;
+        }
+    }
+
+    /// `pull_settings` requests the `vale-ls` section of the client's settings
+    /// with `workspace/configuration` and merges it into our own.
+    async fn pull_settings(&self) {
+        if !self.client_cap(|c| c.configuration) {
+            return;
+        }
+
+        let items = vec![ConfigurationItem {
+            scope_uri: None,
+            section: Some("vale-ls".to_string()),
+        }];
+        match self.client.configuration(items).await {
+            Ok(values) => {
+                for value in values {
+                    self.parse_params(Some(value)).await;
+                }
+            }
+            Err(err) => {
+                self.client
+                    .log_message(
+                        MessageType::ERROR,
+                        format!("Failed to fetch settings: {}", err),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    /// `parse_params` stores the given settings, warning about any that are
+    /// unknown or have a value of the wrong type.
+    async fn parse_params(&self, params: Option<Value>) {
+        for problem in params.iter().flat_map(settings::validate) {
+            self.client.log_message(MessageType::WARNING, problem).await;
+        }
+        if let Some(Value::Object(map)) = params {
+            for (k, v) in map {
+                self.param_map.insert(k.to_string(), v.clone());
+            }
+        }
+    }
+
+    fn get_string(&self, key: &str) -> String {
+        if self.get_setting(key).is_some() {
+            let value = self.get_setting(key).unwrap();
+            if value.is_string() {
+                return value.as_str().unwrap().to_string();
+            }
+        }
+        "".to_string()
+    }
+
+    fn get_setting(&self, key: &str) -> Option<Value> {
+        if self.param_map.contains_key(key) {
+            let value = self.param_map.get(key).unwrap();
+            return Some(value.clone());
+        }
+        None
+    }
+
+    fn update(&self, params: TextDocumentItem) {
+        let rope = ropey::Rope::from_str(&params.text);
+        self.document_map.insert(params.uri.to_string(), rope);
+        if let Some(version) = params.version {
+            self.version_map.insert(params.uri.to_string(), version);
+        }
+        *self
+            .generation_map
+            .entry(params.uri.to_string())
+            .or_insert(0) += 1;
+    }
+
+    /// `version` returns the latest version of the document at `uri` that the
+    /// client sent us.
+    fn version(&self, uri: &Url) -> Option<i32> {
+        self.version_map.get(uri.as_str()).map(|v| *v)
+    }
+
+    /// `generation` counts the versions of a document we've seen, so that lint
+    /// results for an outdated version can be recognized and dropped.
+    fn generation(&self, uri: &Url) -> u64 {
+        self.generation_map.get(uri.as_str()).map_or(0, |g| *g)
+    }
+
+    fn get_ext(&self, uri: Url) -> String {
+        let ext = uri.path().split('.').last().unwrap_or("");
+        if ini::is_config(uri.path()) {
+            return "ini".to_string();
+        } else if vocab::is_vocab(Path::new(uri.path())) {
+            return "vocab".to_string();
+        } else if ext == "yml" {
+            let (config_path, cwd) = self.config_for(&uri);
+            let config = self.cli.config(config_path, cwd);
+            if config.is_ok() {
+                let styles = config.unwrap().styles_path;
+                let p = self.styles(styles);
+                if p.is_rule(uri.path()) || p.has(uri.path()).unwrap_or(false) {
+                    return "yml".to_string();
+                }
+            }
+        }
+        "".to_string()
+    }
+
+    async fn do_sync(&self) {
+        self.set_syncing(true).await;
+        let result = self.cli.sync(self.config_path(), self.root_path());
+        self.set_syncing(false).await;
+        self.invalidate();
+
+        match result {
+            Ok(_) => {
+                self.client
+                    .show_message(MessageType::INFO, "Successfully synced Vale config.")
+                    .await;
+            }
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Failed to sync CLI: {}", e))
+                    .await;
+            }
+        }
+    }
+
+    /// `command_config` resolves the config file for a command given an
+    /// optional document URI argument.
+    fn command_config(&self, uri: Option<&Value>) -> Option<PathBuf> {
+        match uri.and_then(|a| a.as_str()) {
+            Some(uri) => Url::parse(uri)
+                .ok()
+                .and_then(|u| u.to_file_path().ok())
+                .and_then(|fp| self.config_file(&fp)),
+            None if !self.config_path().is_empty() => Some(PathBuf::from(self.config_path())),
+            None => ini::discover(Path::new(&self.root_path())),
+        }
+    }
+
+    /// `do_new_style` creates a style, with a starter rule, in the
+    /// `StylesPath` and offers to enable it.
+    async fn do_new_style(&self, arguments: Vec<Value>) {
+        let name = match arguments.first().and_then(|a| a.as_str()) {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        "No style name provided. Please try again.",
+                    )
+                    .await;
+                return;
+            }
+        };
+
+        let config = match self.command_config(arguments.get(1)) {
+            Some(config) => config,
+            None => {
+                self.client
+                    .show_message(MessageType::ERROR, "No Vale config file found.")
+                    .await;
+                return;
+            }
+        };
+        let cwd = config
+            .parent()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let styles = match self.cli.config(config.display().to_string(), cwd) {
+            Ok(c) => self.styles(c.styles_path),
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Failed to load config: {}", e))
+                    .await;
+                return;
+            }
+        };
+
+        let rule = yml::example("existence").unwrap_or_default();
+        let path = match styles.new_style(&name, rule) {
+            Ok(path) => path,
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Failed to create style: {}", e))
+                    .await;
+                return;
+            }
+        };
+
+        if let Ok(uri) = Url::from_file_path(&path) {
+            self.show_document(uri, None).await;
+        }
+
+        let add = MessageActionItem {
+            title: "Add to BasedOnStyles".to_string(),
+            properties: Default::default(),
+        };
+        let picked = self
+            .client
+            .show_message_request(
+                MessageType::INFO,
+                format!("Created style '{}'. Enable it for all files?", name),
+                Some(vec![
+                    add.clone(),
+                    MessageActionItem {
+                        title: "Not now".to_string(),
+                        properties: Default::default(),
+                    },
+                ]),
+            )
+            .await;
+        if !matches!(picked, Ok(Some(item)) if item.title == add.title) {
+            return;
+        }
+
+        match edit_file(&config, |src| ini::add_style(src, &name)) {
+            Ok(_) => self.relint_all().await,
+            Err(e) => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to update {}: {}", config.display(), e),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    /// `do_new_rule` writes the example rule for an `extends` type to a style
+    /// and opens it.
+    ///
+    /// The arguments are the style, the `extends` type, and, optionally, the
+    /// rule's name and a document URI to resolve the config from.
+    async fn do_new_rule(&self, arguments: Vec<Value>) {
+        let args: Vec<&str> = arguments.iter().filter_map(|a| a.as_str()).collect();
+        if args.len() < 2 {
+            self.client
+                .show_message(
+                    MessageType::ERROR,
+                    "Expected a style and an extends type. Please try again.",
+                )
+                .await;
+            return;
+        }
+
+        let (style, extends) = (args[0], args[1]);
+        let rule = match yml::example(extends) {
+            Some(rule) => rule,
+            None => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Unknown extends type '{}'.", extends),
+                    )
+                    .await;
+                return;
+            }
+        };
+        let name = match args.get(2) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => {
+                let mut chars = extends.chars();
+                chars
+                    .next()
+                    .map_or(String::new(), |c| c.to_uppercase().chain(chars).collect())
+            }
+        };
+
+        let (config_path, cwd) = match self.command_config(arguments.get(3)) {
+            Some(config) => (
+                config.display().to_string(),
+                config
+                    .parent()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+            ),
+            None => (self.config_path(), self.root_path()),
+        };
+        let path = self
+            .cli
+            .config(config_path, cwd)
+            .and_then(|c| self.styles(c.styles_path).new_rule(style, &name, rule));
+
+        match path.map(Url::from_file_path) {
+            Ok(Ok(uri)) => self.show_document(uri, None).await,
+            Ok(Err(_)) => {}
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Failed to create rule: {}", e))
+                    .await;
+            }
+        }
+    }
+
+    /// `do_install_package` adds a package to the `Packages` of the config
+    /// file in use and runs `vale sync` to download it.
+    async fn do_install_package(&self, arguments: Vec<Value>) {
+        let name = match arguments.first().and_then(|a| a.as_str()) {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => {
+                self.client
+                    .show_message(MessageType::ERROR, "No package provided. Please try again.")
+                    .await;
+                return;
+            }
+        };
+
+        let config = match self.command_config(arguments.get(1)) {
+            Some(config) => config,
+            None => {
+                self.client
+                    .show_message(MessageType::ERROR, "No Vale config file found.")
+                    .await;
+                return;
+            }
+        };
+
+        if let Err(e) = edit_file(&config, |src| ini::add_package(src, &name)) {
+            self.client
+                .show_message(
+                    MessageType::ERROR,
+                    format!("Failed to update {}: {}", config.display(), e),
+                )
+                .await;
+            return;
+        }
+
+        let token = self.begin_progress(&format!("Installing {}", name)).await;
+        let cwd = config
+            .parent()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let result = self.cli.sync(config.display().to_string(), cwd);
+        self.end_progress(token).await;
+        self.invalidate();
+
+        match result {
+            Ok(_) => {
+                self.client
+                    .show_message(MessageType::INFO, format!("Installed {}.", name))
+                    .await;
+                self.relint_all().await;
+            }
+            Err(e) => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to install {}: {}", name, e),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    /// `scan_workspace` lints every workspace folder, so that clients can list
+    /// alerts before the files are opened.
+    ///
+    /// It's spawned rather than awaited, since a large workspace can take a
+    /// while.
+    async fn scan_workspace(&self) {
+        if !self.cli.is_installed() || self.pull_diagnostics() {
+            return;
+        }
+
+        let token = self.begin_progress("Scanning workspace").await;
+        let mut count = 0;
+        for folder in self.workspace_roots() {
+            match self.lint_dir_queued(folder.clone()).await {
+                Ok(files) => {
+                    count += files.len();
+                    for (uri, diagnostics) in files {
+                        self.send_diagnostics(uri, diagnostics, None).await;
+                    }
+                }
+                Err(e) => {
+                    self.client
+                        .log_message(
+                            MessageType::ERROR,
+                            format!("Failed to scan {}: {}", folder.display(), e),
+                        )
+                        .await;
+                }
+            }
+        }
+        self.end_progress(token).await;
+
+        self.client
+            .log_message(
+                MessageType::INFO,
+                format!("Scanned workspace: {} file(s) with alerts.", count),
+            )
+            .await;
+    }
+
+    /// `do_uninstall` removes the Vale binary we manage, leaving any other
+    /// installation in place.
+    async fn do_uninstall(&self) {
+        match self.cli.uninstall() {
+            Ok(true) => {
+                self.reset_version();
+                self.send_status().await;
+                self.client
+                    .show_message(MessageType::INFO, "Removed the managed Vale binary.")
+                    .await;
+            }
+            Ok(false) => {
+                self.client
+                    .show_message(MessageType::INFO, "Vale isn't installed by vale-ls.")
+                    .await;
+            }
+            Err(e) => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to uninstall Vale: {}", e),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    /// `do_install_version` installs a specific version of Vale, such as an
+    /// older one to roll back to.
+    async fn do_install_version(&self, arguments: Vec<Value>) {
+        let version = match arguments.first().and_then(|a| a.as_str()) {
+            Some(v) if !v.trim().is_empty() => v.trim().to_string(),
+            _ => {
+                self.client
+                    .show_message(MessageType::ERROR, "No version provided. Please try again.")
+                    .await;
+                return;
+            }
+        };
+
+        let token = self
+            .begin_progress(&format!("Installing Vale {}", version))
+            .await;
+        let result = self.cli.install_to(Some(&version), None).await;
+        self.end_progress(token).await;
+
+        match result {
+            Ok(status) => {
+                self.cli.invalidate();
+                self.reset_version();
+                self.send_status().await;
+                let mut message = status;
+                if self.should_install() {
+                    message += " Set `installVale` to false to keep this version.";
+                }
+                self.client.show_message(MessageType::INFO, message).await;
+                self.relint_all().await;
+            }
+            Err(e) => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to install Vale {}: {}", version, e),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    async fn do_lint_workspace(&self) {
+        if !self.cli.is_installed() {
             self.client
-                .log_message(MessageType::INFO, "No file path found. Is the file saved?")
+                .show_message(MessageType::ERROR, "Vale CLI not installed!")
                 .await;
+            return;
         }
-    }
 
-    async fn init(&self, params: Option<Value>, cwd: String) {
-        self.parse_params(params);
-        if self.should_install() {
-            match self.cli.install_or_update() {
-                Ok(status) => {
-                    self.client.log_message(MessageType::INFO, status).await;
+        let mut count = 0;
+        for folder in self.workspace_roots() {
+            match self.lint_dir_queued(folder).await {
+                Ok(files) => {
+                    count += files.len();
+                    for (uri, diagnostics) in files {
+                        self.send_diagnostics(uri, diagnostics, None).await;
+                    }
                 }
-                Err(err) => {
+                Err(e) => {
                     self.client
-                        .show_message(MessageType::INFO, err.to_string())
-                        .await;
-                    self.client
-                        .log_message(MessageType::ERROR, err.to_string())
+                        .show_message(
+                            MessageType::ERROR,
+                            format!("Failed to lint workspace: {}", e),
+                        )
                         .await;
+                    return;
                 }
             }
         }
-    }
 
-    fn should_install(&self) -> bool {
-        self.get_setting("installVale") == Some(Value::Bool(true))
+        self.client
+            .show_message(
+                MessageType::INFO,
+                format!("Linted workspace: {} file(s) with alerts.", count),
+            )
+            .await;
     }
 
-    fn config_path(&self) -> String {
-        self.get_string("configPath")
-    }
+    async fn do_add_to_vocab(&self, arguments: Vec<Value>) {
+        let args: Vec<&str> = arguments.iter().filter_map(|a| a.as_str()).collect();
+        if args.len() != 4 {
+            self.client
+                .show_message(
+                    MessageType::ERROR,
+                    "Expected a URI, Vocab, term, and list. Please try again.",
+                )
+                .await;
+            return;
+        }
 
-    fn config_filter(&self) -> String {
-        self.get_string("filter")
-    }
+        let (config_path, cwd) = match Url::parse(args[0]) {
+            Ok(uri) => self.config_for(&uri),
+            Err(_) => (self.config_path(), self.root_path()),
+        };
+        let config = match self.cli.config(config_path, cwd) {
+            Ok(config) => config,
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Failed to load config: {}", e))
+                    .await;
+                return;
+            }
+        };
 
-    fn should_sync(&self) -> bool {
-        self.get_setting("syncOnStartup") == Some(Value::Bool(true))
-    }
+        let p = self.styles(config.styles_path);
+        let added = match args[3] {
+            "reject" => p.add_to_reject(args[1], args[2]),
+            _ => p.add_to_accept(args[1], args[2]),
+        };
 
-    fn root_path(&self) -> String {
-        self.get_string("root")
+        match added {
+            Ok(_) => {
+                if let Ok(uri) = Url::parse(args[0]) {
+                    self.relint(&uri).await;
+                }
+            }
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Failed to update Vocab: {}", e))
+                    .await;
+            }
+        }
     }
 
-    fn parse_params(&self, params: Option<Value>) {
-        if let Some(Value::Object(map)) = params {
-            for (k, v) in map {
-                self.param_map.insert(k.to_string(), v.clone());
+    /// `do_add_term` adds a term to a vocabulary's accept or reject list,
+    /// given the vocabulary, the term, and, optionally, a document URI to
+    /// resolve the config from.
+    async fn do_add_term(&self, arguments: Vec<Value>, accept: bool) {
+        let args: Vec<&str> = arguments.iter().filter_map(|a| a.as_str()).collect();
+        if args.len() < 2 || args[0].is_empty() || args[1].trim().is_empty() {
+            self.client
+                .show_message(
+                    MessageType::ERROR,
+                    "Expected a Vocab and a term. Please try again.",
+                )
+                .await;
+            return;
+        }
+
+        let (config_path, cwd) = match self.command_config(arguments.get(2)) {
+            Some(config) => (
+                config.display().to_string(),
+                config
+                    .parent()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+            ),
+            None => (self.config_path(), self.root_path()),
+        };
+        let config = match self.cli.config(config_path, cwd) {
+            Ok(config) => config,
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Failed to load config: {}", e))
+                    .await;
+                return;
+            }
+        };
+
+        let p = self.styles(config.styles_path);
+        let added = match accept {
+            true => p.add_to_accept(args[0], args[1].trim()),
+            false => p.add_to_reject(args[0], args[1].trim()),
+        };
+        match added {
+            Ok(_) => self.relint_all().await,
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Failed to update Vocab: {}", e))
+                    .await;
             }
         }
     }
 
-    fn get_string(&self, key: &str) -> String {
-        if self.get_setting(key).is_some() {
-            let value = self.get_setting(key).unwrap();
-            if value.is_string() {
-                return value.as_str().unwrap().to_string();
+    /// `do_lint_document` lints an open document on demand, which is the only
+    /// way documents are linted when `runMode` is `manual`.
+    async fn do_lint_document(&self, arguments: Vec<Value>) {
+        let uri = match arguments.first().and_then(|a| a.as_str()) {
+            Some(arg) => Url::parse(arg),
+            None => {
+                self.client
+                    .show_message(MessageType::ERROR, "No URI provided. Please try again.")
+                    .await;
+                return;
+            }
+        };
+        let uri = match uri {
+            Ok(uri) => uri,
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Invalid URI: {}", e))
+                    .await;
+                return;
             }
+        };
+
+        if !self.cli.is_installed() {
+            self.client
+                .show_message(MessageType::ERROR, "Vale CLI not installed!")
+                .await;
+            return;
         }
-        "".to_string()
-    }
 
-    fn get_setting(&self, key: &str) -> Option<Value> {
-        if self.param_map.contains_key(key) {
-            let value = self.param_map.get(key).unwrap();
-            return Some(value.clone());
+        // An explicit request always runs Vale again.
+        self.lint_cache.remove(uri.as_str());
+        self.relint(&uri).await;
+        if self.pull_diagnostics() {
+            let _ = self
+                .client
+                .send_request::<request::WorkspaceDiagnosticRefresh>(())
+                .await;
         }
-        None
     }
 
-    fn update(&self, params: TextDocumentItem) {
-        let uri = params.uri.clone();
-        if self.get_ext(uri) != "" {
-            let rope = ropey::Rope::from_str(&params.text);
-            self.document_map
-                .insert(params.uri.to_string(), rope.clone());
+    /// `do_run_rule` lints every open document with only the rule at the given
+    /// URI enabled and reports how many matches it finds, to gauge how noisy
+    /// the rule is.
+    async fn do_run_rule(&self, arguments: Vec<Value>) {
+        let uri = arguments
+            .first()
+            .and_then(|a| a.as_str())
+            .and_then(|a| Url::parse(a).ok());
+        let fp = uri.as_ref().and_then(|uri| uri.to_file_path().ok());
+        let (uri, check) = match (uri, fp.as_deref().and_then(check_name)) {
+            (Some(uri), Some(check)) => (uri, check),
+            _ => {
+                self.client
+                    .show_message(MessageType::ERROR, "No rule provided. Please try again.")
+                    .await;
+                return;
+            }
+        };
+
+        let rule = match self.document_map.get(uri.as_str()) {
+            Some(rope) => rope.to_string(),
+            None => std::fs::read_to_string(fp.unwrap_or_default()).unwrap_or_default(),
+        };
+        let open: Vec<(String, String)> = self
+            .document_map
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().to_string()))
+            .collect();
+        let docs: Vec<(Url, String)> = open
+            .into_iter()
+            .filter_map(|(doc, text)| {
+                let doc = Url::parse(&doc).ok()?;
+                let prose = self.get_ext(doc.clone()).is_empty() && self.should_lint(&doc);
+                prose.then_some((doc, text))
+            })
+            .collect();
+        if docs.is_empty() {
+            self.client
+                .show_message(
+                    MessageType::INFO,
+                    format!("There are no open documents to run {} against.", check),
+                )
+                .await;
+            return;
         }
-    }
 
-    fn get_ext(&self, uri: Url) -> String {
-        let ext = uri.path().split('.').last().unwrap_or("");
-        if uri.path().contains(".vale.ini") {
-            return "ini".to_string();
-        } else if ext == "yml" {
-            let config = self.cli.config(self.config_path(), self.root_path());
-            if config.is_ok() {
-                let styles = config.unwrap().styles_path;
-                let p = styles::StylesPath::new(styles);
-                if p.has(uri.path()).unwrap_or(false) {
-                    return "yml".to_string();
+        let token = self.begin_progress(&format!("Running {}", check)).await;
+        let mut counts = Vec::new();
+        for (doc, text) in &docs {
+            let ext = Path::new(doc.path())
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("txt");
+            match self.cli.test_rule(&check, &rule, text, ext) {
+                Ok(alerts) if !alerts.is_empty() => counts.push((doc, alerts.len())),
+                Ok(_) => {}
+                Err(err) => {
+                    self.end_progress(token).await;
+                    self.client
+                        .show_message(
+                            MessageType::ERROR,
+                            format!("Failed to run {}: {}", check, err),
+                        )
+                        .await;
+                    return;
                 }
             }
         }
-        "".to_string()
+        self.end_progress(token).await;
+
+        counts.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        for (doc, n) in &counts {
+            self.client
+                .log_message(
+                    MessageType::INFO,
+                    format!("{}: {} match(es) in {}", check, n, doc),
+                )
+                .await;
+        }
+        let total: usize = counts.iter().map(|(_, n)| n).sum();
+        self.client
+            .show_message(
+                MessageType::INFO,
+                format!(
+                    "{}: {} match(es) in {} of {} open file(s).",
+                    check,
+                    total,
+                    counts.len(),
+                    docs.len()
+                ),
+            )
+            .await;
     }
 
-    async fn do_sync(&self) {
-        match self.cli.sync(self.config_path(), self.root_path()) {
-            Ok(_) => {
+    /// `do_test_style` runs the fixtures in `<Style>/tests` against their
+    /// rules and reports the results as diagnostics on the rule files.
+    ///
+    /// The argument, if any, is a style name or the URI of a file in the
+    /// style; otherwise, every style in the `StylesPath` is tested.
+    async fn do_test_style(&self, arguments: Vec<Value>) {
+        let arg = arguments.first().and_then(|a| a.as_str()).unwrap_or("");
+        let uri = Url::parse(arg).ok();
+        let (config_path, cwd) = match &uri {
+            Some(uri) => self.config_for(uri),
+            None => (self.config_path(), self.root_path()),
+        };
+        let root = match self.cli.config(config_path, cwd) {
+            Ok(config) => config.styles_path,
+            Err(err) => {
                 self.client
-                    .show_message(MessageType::INFO, "Successfully synced Vale config.")
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to load config: {}", err),
+                    )
                     .await;
+                return;
             }
-            Err(e) => {
+        };
+
+        let name = match uri.and_then(|u| u.to_file_path().ok()) {
+            Some(fp) => fp
+                .strip_prefix(&root)
+                .ok()
+                .and_then(|p| p.components().next())
+                .map(|c| c.as_os_str().to_string_lossy().to_string()),
+            None if !arg.is_empty() => Some(arg.to_string()),
+            None => None,
+        };
+        let styles: Vec<PathBuf> = match name {
+            Some(name) => vec![root.join(name)],
+            None => std::fs::read_dir(&root)
+                .map(|entries| entries.flatten().map(|e| e.path()).collect())
+                .unwrap_or_default(),
+        };
+
+        let fixtures: Vec<fixture::Fixture> =
+            styles.iter().flat_map(|s| fixture::find(s)).collect();
+        if fixtures.is_empty() {
+            self.client
+                .show_message(
+                    MessageType::INFO,
+                    "No fixtures found; add them to `<Style>/tests/<Rule>.md`.",
+                )
+                .await;
+            return;
+        }
+        for style in &styles {
+            self.fixture_map.retain(|uri, _| {
+                let fp = Url::parse(uri).ok().and_then(|u| u.to_file_path().ok());
+                !fp.is_some_and(|fp| fp.starts_with(style))
+            });
+        }
+
+        let token = self.begin_progress("Testing styles").await;
+        let (mut passed, mut failed) = (0, 0);
+        for f in &fixtures {
+            let rule_uri = match Url::from_file_path(&f.rule) {
+                Ok(uri) => uri,
+                Err(_) => continue,
+            };
+            let name = f.path.strip_prefix(&root).unwrap_or(&f.path).display();
+
+            let rule = match self.document_map.get(rule_uri.as_str()) {
+                Some(rope) => rope.to_string(),
+                None => std::fs::read_to_string(&f.rule).unwrap_or_default(),
+            };
+            let text = std::fs::read_to_string(&f.path).unwrap_or_default();
+            let ext = f.path.extension().and_then(|e| e.to_str()).unwrap_or("md");
+
+            let problems = match self.cli.test_rule(&f.check, &rule, &text, ext) {
+                Ok(alerts) => {
+                    let actual = alerts.iter().map(|a| a.line).collect();
+                    fixture::failures(&fixture::expected(&text), &actual)
+                }
+                Err(err) => vec![format!("failed to run: {}", err)],
+            };
+
+            let mut results = Vec::new();
+            if problems.is_empty() {
+                passed += 1;
+                results.push(fixture_diagnostic(
+                    DiagnosticSeverity::INFORMATION,
+                    format!("Passes {}.", name),
+                ));
+            } else {
+                failed += 1;
+                for problem in problems {
+                    self.client
+                        .log_message(MessageType::WARNING, format!("{}: {}", name, problem))
+                        .await;
+                    results.push(fixture_diagnostic(
+                        DiagnosticSeverity::ERROR,
+                        format!("{}: {}.", name, problem),
+                    ));
+                }
+            }
+            self.fixture_map
+                .entry(rule_uri.to_string())
+                .or_default()
+                .extend(results);
+        }
+        self.end_progress(token).await;
+
+        if self.pull_diagnostics() {
+            let _ = self
+                .client
+                .send_request::<request::WorkspaceDiagnosticRefresh>(())
+                .await;
+        } else {
+            let rules: Vec<String> = self.fixture_map.iter().map(|e| e.key().clone()).collect();
+            for uri in rules.iter().filter_map(|r| Url::parse(r).ok()) {
+                let text = match self.document_map.get(uri.as_str()) {
+                    Some(rope) => rope.to_string(),
+                    None => uri
+                        .to_file_path()
+                        .ok()
+                        .and_then(|fp| std::fs::read_to_string(fp).ok())
+                        .unwrap_or_default(),
+                };
+                if let Some(diagnostics) = self.asset_diagnostics(&uri, &text) {
+                    let version = self.version(&uri);
+                    self.send_diagnostics(uri, diagnostics, version).await;
+                }
+            }
+        }
+
+        let kind = if failed > 0 {
+            MessageType::WARNING
+        } else {
+            MessageType::INFO
+        };
+        self.client
+            .show_message(
+                kind,
+                format!("Style tests: {} passed, {} failed.", passed, failed),
+            )
+            .await;
+    }
+
+    /// `do_show_config` returns the configuration Vale resolves for the given
+    /// document URI (or the workspace) as pretty-printed JSON, for the client
+    /// to show in a read-only document.
+    async fn do_show_config(&self, arguments: Vec<Value>) -> Option<Value> {
+        let uri = arguments
+            .first()
+            .and_then(|a| a.as_str())
+            .and_then(|a| Url::parse(a).ok());
+        let (config_path, cwd) = match &uri {
+            Some(uri) => self.config_for(uri),
+            None => (self.config_path(), self.root_path()),
+        };
+
+        let config = self
+            .cli
+            .resolved_config(&config_path, &cwd)
+            .and_then(|c| Ok(serde_json::to_string_pretty(&c)?));
+        match config {
+            Ok(config) => Some(Value::String(config)),
+            Err(err) => {
                 self.client
-                    .show_message(MessageType::ERROR, format!("Failed to sync CLI: {}", e))
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("Failed to load config: {}", err),
+                    )
                     .await;
+                None
             }
         }
     }
@@ -539,11 +4237,21 @@ impl Backend {
             return;
         }
 
-        let arg = arguments[0].as_str().unwrap().to_string();
-        let uri = Url::parse(&arg).unwrap().to_file_path().unwrap();
+        let uri = match arguments[0]
+            .as_str()
+            .and_then(|arg| Url::parse(arg).ok())
+            .and_then(|uri| utils::file_path(&uri))
+        {
+            Some(path) => path,
+            None => {
+                self.client
+                    .show_message(MessageType::ERROR, "Invalid URI provided.")
+                    .await;
+                return;
+            }
+        };
 
-        let ext = uri.extension().unwrap().to_str().unwrap();
-        if ext != "yml" {
+        if uri.extension().and_then(|e| e.to_str()) != Some("yml") {
             self.client
                 .show_message(
                     MessageType::ERROR,
@@ -553,11 +4261,117 @@ impl Backend {
             return;
         }
 
-        let resp = self.cli.upload_rule(
-            self.config_path(),
-            self.root_path(),
-            uri.to_str().unwrap().to_string(),
-        );
+        // NOTE: The second argument is `false` when the client only wants to
+        // see the compiled pattern rather than open it on Regex101.
+        let upload = arguments.get(1).and_then(|v| v.as_bool()).unwrap_or(true);
+        self.compile_rule(&uri, self.root_for(&uri), upload).await;
+    }
+
+    /// `do_compile_selection` compiles a single pattern, selected from a rule's
+    /// `tokens` or `swap`, given the rule's URI, the selected range, and,
+    /// optionally, `false` to show the result rather than open it on
+    /// Regex101.
+    async fn do_compile_selection(&self, arguments: Vec<Value>) {
+        let uri = arguments
+            .first()
+            .and_then(|v| v.as_str())
+            .and_then(|s| Url::parse(s).ok());
+        let range: Option<Range> = arguments
+            .get(1)
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+        let (uri, range) = match (uri, range) {
+            (Some(uri), Some(range)) => (uri, range),
+            _ => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        "No URI or range provided. Please try again.",
+                    )
+                    .await;
+                return;
+            }
+        };
+        let upload = arguments.get(2).and_then(|v| v.as_bool()).unwrap_or(true);
+
+        let rule = match self.selected_rule(&uri, range) {
+            Ok(rule) => rule,
+            Err(err) => {
+                self.client
+                    .show_message(MessageType::ERROR, err.to_string())
+                    .await;
+                return;
+            }
+        };
+
+        let fp = uri.to_file_path().unwrap_or_default();
+        self.compile_rule(rule.path(), self.root_for(&fp), upload)
+            .await;
+    }
+
+    /// `selected_rule` writes a copy of the rule at `uri` whose only pattern is
+    /// the one selected by `range` to a temporary file.
+    fn selected_rule(&self, uri: &Url, range: Range) -> std::result::Result<NamedTempFile, Error> {
+        let src = match self.document_map.get(uri.as_str()) {
+            Some(rope) => rope.to_string(),
+            None => std::fs::read_to_string(uri.to_file_path().unwrap_or_default())?,
+        };
+
+        let line = range.start.line as usize;
+        let key = yml::block(&src, line).map(|(key, _)| key);
+        if range.start.line != range.end.line
+            || range.start.character == range.end.character
+            || !matches!(key.as_deref(), Some("tokens") | Some("swap"))
+        {
+            return Err(Error::from(
+                "Select a single pattern from the rule's `tokens` or `swap`.",
+            ));
+        }
+
+        let text: String = src
+            .lines()
+            .nth(line)
+            .unwrap_or("")
+            .chars()
+            .skip(range.start.character as usize)
+            .take((range.end.character - range.start.character) as usize)
+            .collect();
+        let token = yml::selected_token(&text, key.as_deref() == Some("swap"));
+        let rule = yml::with_token(&src, &token)
+            .ok_or(Error::from("Unable to read the rule; is it valid YAML?"))?;
+
+        let mut file = tempfile::Builder::new().suffix(".yml").tempfile()?;
+        file.write_all(rule.as_bytes())?;
+        Ok(file)
+    }
+
+    /// `compile_rule` compiles the rule at `path`, using `cwd` to find the
+    /// config, and either shows the resulting pattern or opens it on
+    /// Regex101.
+    async fn compile_rule(&self, path: &Path, cwd: String, upload: bool) {
+        let rule = path.to_str().unwrap_or_default().to_string();
+        if !upload {
+            match self
+                .cli
+                .compile(self.config_path(), cwd.clone(), rule.clone())
+            {
+                Ok(rule) => {
+                    self.client
+                        .show_message(MessageType::INFO, format!("Compiled: {}", rule.pattern))
+                        .await;
+                }
+                Err(e) => {
+                    self.client
+                        .show_message(MessageType::ERROR, format!("Failed to compile rule: {}", e))
+                        .await;
+                }
+            }
+            return;
+        }
+
+        let resp = self
+            .cli
+            .upload_rule(self.config_path(), cwd.clone(), rule.clone())
+            .await;
 
         match resp {
             Ok(r) => {
@@ -589,3 +4403,82 @@ impl Backend {
         }
     }
 }
+
+/// `fixture_diagnostic` reports the result of a rule's fixture at the top of
+/// the rule.
+fn fixture_diagnostic(severity: DiagnosticSeverity, message: String) -> Diagnostic {
+    Diagnostic {
+        range: Range::default(),
+        severity: Some(severity),
+        code: Some(NumberOrString::String("fixture".to_string())),
+        source: Some("vale-ls".to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+/// `check_name` returns the name of the check (e.g., `Microsoft.Passive`)
+/// defined by the rule file at `path`.
+fn check_name(path: &Path) -> Option<String> {
+    let style = path.parent()?.file_name()?.to_str()?;
+    let rule = path.file_stem()?.to_str()?;
+    Some(format!("{}.{}", style, rule))
+}
+
+/// `non_overlapping` sorts `edits` and drops any that overlap an earlier one,
+/// since clients reject overlapping edits; the rest are left for a later run.
+fn non_overlapping(mut edits: Vec<TextEdit>) -> Vec<TextEdit> {
+    edits.sort_by_key(|e| (e.range.start.line, e.range.start.character));
+    let mut applied: Vec<TextEdit> = Vec::new();
+    for edit in edits {
+        if let Some(last) = applied.last() {
+            if last.range.end > edit.range.start {
+                continue;
+            }
+        }
+        applied.push(edit);
+    }
+    applied
+}
+
+/// `rule_location` returns the `message` line of the rule file at `path`.
+fn rule_location(path: PathBuf) -> Option<Location> {
+    let src = std::fs::read_to_string(&path).ok()?;
+    let range = yml::find_key(&src, "message").unwrap_or_default();
+    Some(Location::new(Url::from_file_path(path).ok()?, range))
+}
+
+/// `edit_file` applies the edit, if any, that `f` computes for the contents
+/// of the file at `path`.
+fn edit_file(path: &Path, f: impl Fn(&str) -> Option<TextEdit>) -> std::result::Result<(), Error> {
+    let src = std::fs::read_to_string(path)?;
+    let edit = match f(&src) {
+        Some(edit) => edit,
+        None => return Ok(()),
+    };
+
+    let mut rope = Rope::from_str(&src);
+    let start = edit.range.start;
+    let idx = rope.line_to_char(start.line as usize) + start.character as usize;
+    if edit.new_text.ends_with('\n')
+        && idx == rope.len_chars()
+        && !src.is_empty()
+        && !src.ends_with('\n')
+    {
+        rope.insert(idx, "\n");
+        rope.insert(idx + 1, &edit.new_text);
+    } else {
+        rope.insert(idx, &edit.new_text);
+    }
+
+    std::fs::write(path, rope.to_string())?;
+    Ok(())
+}
+
+/// `plural` formats a count of `noun`s, such as "1 error" or "2 warnings".
+fn plural(n: usize, noun: &str) -> String {
+    match n {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", n, noun),
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The settings a client can send in `initializationOptions`,
+/// `workspace/didChangeConfiguration`, or in response to
+/// `workspace/configuration`.
+///
+/// The server reads each setting as it's needed; this only describes what's
+/// accepted so that `validate` can point out mistakes.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Settings {
+    pub install_vale: Option<bool>,
+    pub vale_path: Option<String>,
+    pub binary_dir: Option<String>,
+    pub arch: Option<String>,
+    pub update_channel: Option<String>,
+    pub vale_args: Option<Vec<String>>,
+    pub config_path: Option<String>,
+    /// Config files by workspace folder.
+    pub config_paths: Option<HashMap<String, String>>,
+    pub filter: Option<String>,
+    pub sync_on_startup: Option<bool>,
+    pub sync_on_change: Option<bool>,
+    pub run_mode: Option<RunMode>,
+    pub lint_on_change: Option<bool>,
+    pub lint_delay: Option<u64>,
+    pub max_concurrent_lints: Option<u64>,
+    pub max_file_size: Option<u64>,
+    pub region_lint_threshold: Option<u64>,
+    pub enabled_file_types: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    /// LSP severities (e.g., `information`) by Vale severity.
+    pub severities: Option<HashMap<String, String>>,
+    pub apply_fixes_on_save: Option<bool>,
+    pub fix_on_will_save: Option<bool>,
+    pub scan_workspace_on_startup: Option<bool>,
+    pub clear_diagnostics_on_close: Option<bool>,
+    pub regex101_flavor: Option<String>,
+    pub offline: Option<bool>,
+    pub proxy: Option<String>,
+    #[serde(rename = "packageCacheTTL")]
+    pub package_cache_ttl: Option<u64>,
+    pub network_timeout: Option<u64>,
+    pub download_retries: Option<u64>,
+    pub log_file: Option<String>,
+    pub log_level: Option<String>,
+    /// Read by clients rather than the server (e.g., VS Code's
+    /// `trace.server`).
+    pub trace: Option<Value>,
+}
+
+/// `RunMode` controls when documents are linted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RunMode {
+    /// Lint when a document is opened or saved.
+    OnSave,
+    /// Also lint unsaved changes, after `lintDelay` milliseconds.
+    OnType,
+    /// Only lint when asked to by `cli.lintDocument`.
+    Manual,
+}
+
+/// `validate` checks the settings in `params`, describing each key that's
+/// unknown or has a value of the wrong type.
+pub(crate) fn validate(params: &Value) -> Vec<String> {
+    let map = match params {
+        Value::Object(map) => map,
+        Value::Null => return vec![],
+        _ => return vec!["Expected the settings to be an object.".to_string()],
+    };
+
+    let mut problems = Vec::new();
+    for (key, value) in map {
+        let single = Value::Object([(key.clone(), value.clone())].into_iter().collect());
+        match serde_json::from_value::<Settings>(single) {
+            Ok(_) => {}
+            Err(err) if err.to_string().starts_with("unknown field") => {
+                problems.push(format!("Unknown setting `{}`; it will be ignored.", key));
+            }
+            Err(err) => problems.push(format!("Invalid value for `{}`: {}.", key, err)),
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn validation() {
+        assert!(validate(&json!({
+            "installVale": true,
+            "configPaths": {"docs": "docs/.vale.ini"},
+            "runMode": "onType",
+            "packageCacheTTL": 60,
+            "filter": null,
+        }))
+        .is_empty());
+
+        let problems = validate(&json!({
+            "installVale": "true",
+            "instalVale": true,
+            "runMode": "always",
+        }));
+        assert_eq!(problems.len(), 3);
+        assert_eq!(
+            problems[0],
+            "Unknown setting `instalVale`; it will be ignored."
+        );
+        assert_eq!(
+            problems[1],
+            "Invalid value for `installVale`: invalid type: string \"true\", expected a boolean."
+        );
+        assert!(problems[2].starts_with("Invalid value for `runMode`: unknown variant `always`"));
+    }
+}
@@ -1,7 +1,15 @@
 use core::fmt;
-use std::{fs, path::PathBuf};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use serde::Serialize;
 
 use crate::error::Error;
+use crate::vocab;
+
+/// The rules of Vale's built-in style.
+pub const VALE_RULES: [&str; 4] = ["Avoid", "Repetition", "Spelling", "Terms"];
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum EntryType {
@@ -18,9 +26,40 @@ pub struct PathEntry {
     pub kind: EntryType,
 }
 
+/// A summary of a `StylesPath`, for clients that display its contents.
+#[derive(Debug, Serialize)]
+pub struct Tree {
+    pub path: PathBuf,
+    pub styles: Vec<StyleNode>,
+    pub vocab: Vec<VocabNode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StyleNode {
+    pub name: String,
+    pub path: PathBuf,
+    pub rules: Vec<RuleNode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleNode {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VocabNode {
+    pub name: String,
+    pub path: PathBuf,
+    pub accept: usize,
+    pub reject: usize,
+}
+
 #[derive(Debug)]
 pub struct StylesPath {
     root: PathBuf,
+    /// The entries found by the last walk of `root`, until something changes.
+    cache: RwLock<Option<Vec<PathEntry>>>,
 }
 
 impl fmt::Display for EntryType {
@@ -36,11 +75,15 @@ impl fmt::Display for EntryType {
 /// `StylesPath` provides an interface for managing a directory of styles.
 impl StylesPath {
     pub fn new(root: PathBuf) -> StylesPath {
-        StylesPath { root }
+        StylesPath {
+            root,
+            cache: RwLock::new(None),
+        }
     }
 
     pub fn set_path(&mut self, path: PathBuf) {
         self.root = path;
+        self.invalidate();
     }
 
     pub fn path(&self) -> PathBuf {
@@ -67,7 +110,7 @@ impl StylesPath {
     pub fn get_styles(&self) -> Result<Vec<PathEntry>, Error> {
         let mut styles = vec![PathEntry {
             name: "Vale".to_string(),
-            size: 4,
+            size: VALE_RULES.len(),
             path: "".into(),
             kind: EntryType::Style,
         }];
@@ -76,11 +119,88 @@ impl StylesPath {
         Ok(styles)
     }
 
+    /// Returns the path to the YAML file defining `check` (e.g.,
+    /// `Microsoft.Passive`), if it exists.
+    pub fn rule_path(&self, check: &str) -> Option<PathBuf> {
+        let (style, rule) = check.split_once('.')?;
+        let path = self.root.join(style).join(format!("{}.yml", rule));
+        if path.is_file() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the names of the rules in the style `name`, or `None` if
+    /// there's no such style.
+    pub fn rules(&self, name: &str) -> Option<Vec<String>> {
+        if name == "Vale" {
+            return Some(VALE_RULES.iter().map(|r| r.to_string()).collect());
+        }
+
+        let style = self
+            .get(EntryType::Style)
+            .ok()?
+            .into_iter()
+            .find(|e| e.name == name)?;
+        let rules = self
+            .files(&style.name, EntryType::Style)
+            .ok()?
+            .iter()
+            .filter_map(|p| p.file_stem()?.to_str().map(str::to_string))
+            .collect();
+        Some(rules)
+    }
+
+    /// Returns the files that make up the style or vocabulary named `name`:
+    /// the rules of a style or the `accept.txt` and `reject.txt` files of a
+    /// vocabulary.
+    pub fn files(&self, name: &str, kind: EntryType) -> Result<Vec<PathBuf>, Error> {
+        let entry = self.get(kind.clone())?.into_iter().find(|e| e.name == name);
+        let dir = match entry {
+            Some(e) => e.path,
+            None => return Ok(vec![]),
+        };
+
+        let mut files: Vec<PathBuf> = match kind {
+            EntryType::Vocab => vocab::LISTS
+                .iter()
+                .map(|f| dir.join(f))
+                .filter(|p| vocab::is_vocab(p) && p.is_file())
+                .collect(),
+            _ => self
+                .index_dir(dir, EntryType::Rule)?
+                .into_iter()
+                .map(|e| e.path)
+                .collect(),
+        };
+        files.sort();
+
+        Ok(files)
+    }
+
     pub fn has(&self, path: &str) -> Result<bool, Error> {
         let idx = self.index()?;
         Ok(idx.iter().any(|e| e.path.to_string_lossy() == path))
     }
 
+    /// Returns whether `path` is, or would be, a rule in one of the styles:
+    /// a `.yml` file directly inside a style directory.
+    pub fn is_rule(&self, path: &str) -> bool {
+        let path = Path::new(path);
+        if path.extension().unwrap_or_default() != "yml" {
+            return false;
+        }
+
+        let style = match path.parent() {
+            Some(style) => style,
+            None => return false,
+        };
+        let name = self.entry_name(style.to_path_buf());
+        style.parent() == Some(self.root.as_path())
+            && !["Vocab", "config", ".vale-config"].contains(&name.as_str())
+    }
+
     fn get(&self, kind: EntryType) -> Result<Vec<PathEntry>, Error> {
         let idx = self.index()?;
         Ok(idx
@@ -90,28 +210,148 @@ impl StylesPath {
             .collect())
     }
 
-    fn add_to_vocab(&self, name: &str, term: &str, accept: bool) -> Result<(), Error> {
-        let mut path = self.root.join("Vocab").join(name);
+    /// Returns the styles (with their rules) and vocabularies (with their
+    /// term counts) in the `StylesPath`.
+    pub fn tree(&self) -> Result<Tree, Error> {
+        let idx = self.index()?;
 
-        if accept {
-            path = path.join("accept.txt");
-        } else {
-            path = path.join("reject.txt");
+        let mut styles: Vec<StyleNode> = idx
+            .iter()
+            .filter(|e| e.kind == EntryType::Style)
+            .map(|style| {
+                let mut rules: Vec<RuleNode> = idx
+                    .iter()
+                    .filter(|e| e.kind == EntryType::Rule && e.path.parent() == Some(&style.path))
+                    .map(|rule| RuleNode {
+                        name: rule.name.trim_end_matches(".yml").to_string(),
+                        path: rule.path.clone(),
+                    })
+                    .collect();
+                rules.sort_by(|a, b| a.name.cmp(&b.name));
+                StyleNode {
+                    name: style.name.clone(),
+                    path: style.path.clone(),
+                    rules,
+                }
+            })
+            .collect();
+        styles.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let terms = |path: PathBuf| {
+            fs::read_to_string(path)
+                .map(|s| s.lines().filter(|l| !l.trim().is_empty()).count())
+                .unwrap_or(0)
+        };
+        let [accept, reject] = vocab::LISTS;
+        let mut vocab: Vec<VocabNode> = idx
+            .iter()
+            .filter(|e| e.kind == EntryType::Vocab)
+            .map(|v| VocabNode {
+                name: v.name.clone(),
+                path: v.path.clone(),
+                accept: terms(v.path.join(accept)),
+                reject: terms(v.path.join(reject)),
+            })
+            .collect();
+        vocab.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Tree {
+            path: self.root.clone(),
+            styles,
+            vocab,
+        })
+    }
+
+    /// Creates the style `name` with a `meta.json` and the given starter rule,
+    /// returning the rule's path.
+    pub fn new_style(&self, name: &str, rule: &str) -> Result<PathBuf, Error> {
+        let dir = self.root.join(name);
+        if name.is_empty() || name.contains(['/', '\\', '.']) {
+            return Err(Error::from(format!("'{}' isn't a valid style name.", name)));
+        } else if dir.exists() {
+            return Err(Error::from(format!("'{}' already exists.", name)));
+        }
+
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join("meta.json"),
+            "{\n  \"feed\": \"\",\n  \"vale_version\": \">=3.0.0\"\n}\n",
+        )?;
+
+        let path = dir.join("Example.yml");
+        fs::write(&path, rule)?;
+        self.invalidate();
+        Ok(path)
+    }
+
+    /// Writes a rule named `name` to the existing style `style`, returning its
+    /// path. If `name` is taken, a number is appended to it.
+    pub fn new_rule(&self, style: &str, name: &str, rule: &str) -> Result<PathBuf, Error> {
+        let dir = self.root.join(style);
+        if style.is_empty() || style.contains(['/', '\\', '.']) || !dir.is_dir() {
+            return Err(Error::from(format!("Style '{}' not found.", style)));
+        } else if name.is_empty() || name.contains(['/', '\\', '.']) {
+            return Err(Error::from(format!("'{}' isn't a valid rule name.", name)));
+        }
+
+        let mut path = dir.join(format!("{}.yml", name));
+        let mut n = 1;
+        while path.exists() {
+            n += 1;
+            path = dir.join(format!("{}{}.yml", name, n));
+        }
+
+        fs::write(&path, rule)?;
+        self.invalidate();
+        Ok(path)
+    }
+
+    /// Returns the directory of the vocabulary `name`: the existing one, if
+    /// any, or else where Vale 3 expects it (`config/vocabularies`).
+    pub fn vocab_dir(&self, name: &str) -> PathBuf {
+        let legacy = self.root.join("Vocab").join(name);
+        if legacy.is_dir() {
+            return legacy;
         }
+        self.root.join("config").join("vocabularies").join(name)
+    }
 
-        let content = fs::read_to_string(path.clone())?;
-        let mut lines = content.lines().collect::<Vec<_>>();
+    fn add_to_vocab(&self, name: &str, term: &str, accept: bool) -> Result<(), Error> {
+        let [accepted, rejected] = vocab::LISTS;
+        let path = self
+            .vocab_dir(name)
+            .join(if accept { accepted } else { rejected });
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
 
-        lines.push(term);
-        lines.sort();
+        let mut content = fs::read_to_string(path.clone()).unwrap_or_default();
+        content.push('\n');
+        content.push_str(term);
 
-        let content = lines.join("\n");
-        fs::write(path, content)?;
+        fs::write(path, vocab::normalize(&content))?;
+        self.invalidate();
 
         Ok(())
     }
 
+    /// Forgets the cached index, so that the next lookup walks `root` again.
+    pub fn invalidate(&self) {
+        *self.cache.write().unwrap() = None;
+    }
+
     fn index(&self) -> Result<Vec<PathEntry>, Error> {
+        if let Some(entries) = self.cache.read().unwrap().as_ref() {
+            return Ok(entries.clone());
+        }
+
+        let entries = self.walk()?;
+        *self.cache.write().unwrap() = Some(entries.clone());
+        Ok(entries)
+    }
+
+    fn walk(&self) -> Result<Vec<PathEntry>, Error> {
         let subdirs = fs::read_dir(self.path())?;
         let mut entries = Vec::new();
 
@@ -124,6 +364,11 @@ impl StylesPath {
                 continue;
             } else if dir_name == "Vocab" && path.is_dir() {
                 entries.append(&mut self.index_dir(path.clone(), EntryType::Vocab)?);
+            } else if dir_name == "config" {
+                let vocab = path.join("vocabularies");
+                if vocab.is_dir() {
+                    entries.append(&mut self.index_dir(vocab, EntryType::Vocab)?);
+                }
             } else if path.is_dir() {
                 entries.push(PathEntry {
                     name: dir_name,
@@ -194,4 +439,80 @@ mod tests {
         assert_eq!(style.name, "Test");
         assert_eq!(style.size, 1);
     }
+
+    #[test]
+    fn files() {
+        let p = StylesPath::new(PathBuf::from(STYLES));
+
+        let rules = p.files("Test", EntryType::Style).unwrap();
+        assert_eq!(rules, vec![PathBuf::from(STYLES).join("Test/Rule.yml")]);
+
+        let vocab = p.files("LSP", EntryType::Vocab).unwrap();
+        assert_eq!(vocab.len(), 2);
+
+        assert!(p.files("Missing", EntryType::Style).unwrap().is_empty());
+
+        assert_eq!(p.vocab_dir("LSP"), PathBuf::from(STYLES).join("Vocab/LSP"));
+        assert_eq!(
+            p.vocab_dir("New"),
+            PathBuf::from(STYLES).join("config/vocabularies/New")
+        );
+    }
+
+    #[test]
+    fn tree() {
+        let tree = StylesPath::new(PathBuf::from(STYLES)).tree().unwrap();
+
+        let names: Vec<&str> = tree.styles.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Readability", "Test"]);
+        assert_eq!(tree.styles[1].rules[0].name, "Rule");
+
+        assert_eq!(tree.vocab[0].name, "LSP");
+        assert!(tree.vocab[0].accept > 0);
+    }
+
+    #[test]
+    fn new_style() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let p = StylesPath::new(root.to_path_buf());
+
+        let rule = p.new_style("House", "extends: existence\n").unwrap();
+        assert_eq!(rule, root.join("House/Example.yml"));
+        assert!(root.join("House/meta.json").is_file());
+        assert!(p.new_style("House", "").is_err());
+        assert!(p.new_style("../House", "").is_err());
+
+        assert_eq!(p.files("House", EntryType::Style).unwrap().len(), 1);
+        let next = p
+            .new_rule("House", "Example", "extends: existence\n")
+            .unwrap();
+        assert_eq!(next, root.join("House/Example2.yml"));
+        assert_eq!(p.files("House", EntryType::Style).unwrap().len(), 2);
+        assert!(p.new_rule("Missing", "Example", "").is_err());
+    }
+
+    #[test]
+    fn is_rule() {
+        let p = StylesPath::new(PathBuf::from(STYLES));
+
+        assert!(p.is_rule(".github/styles/Test/Rule.yml"));
+        assert!(p.is_rule(".github/styles/Test/New.yml"));
+        assert!(!p.is_rule(".github/styles/Vocab/New.yml"));
+        assert!(!p.is_rule(".github/styles/Test/New.md"));
+        assert!(!p.is_rule(".github/New.yml"));
+    }
+
+    #[test]
+    fn rule_path() {
+        let p = StylesPath::new(PathBuf::from(STYLES));
+
+        assert!(p.rule_path("Test.Rule").is_some());
+        assert!(p.rule_path("Test.Missing").is_none());
+        assert!(p.rule_path("Vale").is_none());
+
+        assert_eq!(p.rules("Test"), Some(vec!["Rule".to_string()]));
+        assert_eq!(p.rules("Vale").unwrap().len(), VALE_RULES.len());
+        assert_eq!(p.rules("Google"), None);
+    }
 }
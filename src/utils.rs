@@ -1,6 +1,12 @@
-use std::{env, str::FromStr};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::{env, fs, str::FromStr};
 
-use ropey::Rope;
+use dashmap::DashMap;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ropey::{Rope, RopeSlice};
+use serde_json::Value;
 use tower_lsp::lsp_types::*;
 
 use crate::pkg;
@@ -14,19 +20,505 @@ pub(crate) fn make_title(action: String, matched: String, fix: String) -> String
     }
 }
 
-pub(crate) fn vale_arch() -> String {
-    let platform = match env::consts::OS {
+/// `vocab_lists` returns the Vocab lists a check's matches can be added to.
+///
+/// Spelling alerts can only be accepted, while terminology alerts can go
+/// either way.
+pub(crate) fn vocab_lists(check: &str) -> Vec<&'static str> {
+    let rule = check.rsplit('.').next().unwrap_or("");
+    if rule.contains("Spelling") {
+        vec!["accept"]
+    } else if rule.contains("Terms") || rule == "Avoid" {
+        vec!["accept", "reject"]
+    } else {
+        vec![]
+    }
+}
+
+/// `comment_directive` returns the comments that turn `check` off and back on
+/// for the given file extension, along with whether they can be placed inline.
+pub(crate) fn comment_directive(ext: &str, check: &str) -> (String, String, bool) {
+    let off = format!("vale {} = NO", check);
+    let on = format!("vale {} = YES", check);
+    match ext {
+        "md" | "mdx" | "markdown" | "html" | "htm" | "xml" => (
+            format!("<!-- {} -->", off),
+            format!("<!-- {} -->", on),
+            true,
+        ),
+        "adoc" | "asciidoc" => (
+            format!("pass:[<!-- {} -->]", off),
+            format!("pass:[<!-- {} -->]", on),
+            false,
+        ),
+        "rst" => (format!(".. {}", off), format!(".. {}", on), false),
+        "org" | "py" | "rb" | "sh" | "pl" | "r" | "yml" | "yaml" | "toml" => {
+            (format!("# {}", off), format!("# {}", on), false)
+        }
+        _ => (format!("// {}", off), format!("// {}", on), false),
+    }
+}
+
+/// `directive_edits` wraps the alert's range in comments that disable its
+/// check, either inline or on the surrounding lines.
+pub(crate) fn directive_edits(
+    ext: &str,
+    alert: &vale::ValeAlert,
+    rope: &Rope,
+    encoding: &PositionEncodingKind,
+) -> Vec<TextEdit> {
+    let (off, on, inline) = comment_directive(ext, &alert.check);
+    let range = alert_to_range(alert, Some(rope), encoding);
+
+    if inline {
+        return vec![
+            TextEdit {
+                range: Range::new(range.start, range.start),
+                new_text: off,
+            },
+            TextEdit {
+                range: Range::new(range.end, range.end),
+                new_text: on,
+            },
+        ];
+    }
+
+    let context = rope.line(range.start.line as usize);
+    let line = context.to_string();
+    let content = line.trim_end_matches(['\r', '\n']);
+    let indent: String = content.chars().take_while(|c| c.is_whitespace()).collect();
+
+    // reStructuredText comments need a blank line to end the block.
+    let gap = if ext == "rst" { "\n" } else { "" };
+
+    let start = Position::new(range.start.line, 0);
+    let end = Position::new(
+        range.start.line,
+        char_to_column(context, content.chars().count(), encoding),
+    );
+
+    vec![
+        TextEdit {
+            range: Range::new(start, start),
+            new_text: format!("{}{}\n{}", indent, off, gap),
+        },
+        TextEdit {
+            range: Range::new(end, end),
+            new_text: format!("\n{}{}{}", gap, indent, on),
+        },
+    ]
+}
+
+/// `metrics_summary` renders a one-line readability summary of a document.
+pub(crate) fn metrics_summary(m: &vale::ValeMetrics) -> String {
+    format!(
+        "{} words · {} sentences ({:.1} words/sentence) · Flesch reading ease: {:.1}",
+        m.words,
+        m.sentences,
+        m.words_per_sentence(),
+        m.flesch_reading_ease()
+    )
+}
+
+/// The language IDs linted when `enabledFileTypes` isn't set.
+pub(crate) const DEFAULT_FILE_TYPES: [&str; 5] =
+    ["markdown", "rst", "asciidoc", "text", "gitcommit"];
+
+/// `language_id` guesses the language ID a client would give the file at
+/// `path`, for files that aren't open.
+pub(crate) fn language_id(path: &str) -> &'static str {
+    let name = path.rsplit('/').next().unwrap_or("");
+    if name == "COMMIT_EDITMSG" {
+        return "gitcommit";
+    }
+
+    match name.rsplit_once('.').map_or("", |(_, ext)| ext) {
+        "md" | "markdown" | "mdown" | "mkd" => "markdown",
+        "rst" | "rest" => "rst",
+        "adoc" | "asciidoc" | "asc" => "asciidoc",
+        "txt" | "text" => "text",
+        "html" | "htm" => "html",
+        "xml" | "dita" => "xml",
+        "org" => "org",
+        _ => "",
+    }
+}
+
+/// `language_ext` returns the file extension Vale should use for text in the
+/// given language, for documents that don't have one (such as untitled ones).
+pub(crate) fn language_ext(language: &str) -> Option<&'static str> {
+    match language {
+        "markdown" => Some("md"),
+        "mdx" => Some("mdx"),
+        "rst" | "restructuredtext" => Some("rst"),
+        "asciidoc" => Some("adoc"),
+        "text" | "plaintext" | "gitcommit" => Some("txt"),
+        "html" => Some("html"),
+        "xml" => Some("xml"),
+        "org" => Some("org"),
+        "latex" => Some("tex"),
+        _ => None,
+    }
+}
+
+/// `file_path` returns the (normalized) path of a `file:` URI.
+pub(crate) fn file_path(uri: &Url) -> Option<PathBuf> {
+    uri.to_file_path().ok().map(|p| normalize_path(&p))
+}
+
+/// `normalize_path` simplifies a Windows extended-length path, such as
+/// `\\?\C:\docs` or `\\?\UNC\server\share`, to its usual form, which Vale
+/// (like most programs) accepts as an argument or working directory.
+///
+/// Paths that need the prefix (e.g., ones longer than `MAX_PATH`) and all
+/// other paths are returned as-is.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let s = match path.to_str() {
+        Some(s) => s,
+        None => return path.to_path_buf(),
+    };
+
+    let simplified = if let Some(rest) = s.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = s.strip_prefix(r"\\?\") {
+        let drive = rest.as_bytes();
+        if drive.len() < 3 || !drive[0].is_ascii_alphabetic() || &drive[1..3] != b":\\" {
+            return path.to_path_buf();
+        }
+        rest.to_string()
+    } else {
+        return path.to_path_buf();
+    };
+
+    // Without the prefix, Windows would treat `/` as a separator, resolve `.`
+    // and `..`, and trim trailing dots and spaces, changing what the path
+    // refers to.
+    let literal = simplified.contains('/')
+        || simplified
+            .split('\\')
+            .any(|c| c.ends_with('.') || c.ends_with(' '));
+    if simplified.len() >= 260 || literal {
+        return path.to_path_buf();
+    }
+    PathBuf::from(simplified)
+}
+
+/// `headings` returns the (0-based) lines of the section headings in a
+/// Markdown, reStructuredText, or AsciiDoc document.
+pub(crate) fn headings(text: &str, language: &str) -> Vec<usize> {
+    let lines: Vec<&str> = text.lines().collect();
+    let underline = |line: &str, chars: &str| {
+        let line = line.trim_end();
+        line.len() >= 2
+            && line.chars().all(|c| c == line.chars().next().unwrap())
+            && chars.contains(line.chars().next().unwrap())
+    };
+
+    let mut found = Vec::new();
+    let mut fence: Option<String> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let delimiter = match language {
+            "markdown" => ["```", "~~~"].into_iter().find(|d| trimmed.starts_with(d)),
+            "asciidoc" => ["----", "....", "```", "++++"]
+                .into_iter()
+                .find(|d| line.trim_end() == *d || (*d == "```" && line.starts_with(d))),
+            _ => None,
+        };
+        if let Some(d) = delimiter {
+            match &fence {
+                Some(open) if open == d => fence = None,
+                None => fence = Some(d.to_string()),
+                _ => {}
+            }
+            continue;
+        } else if fence.is_some() {
+            continue;
+        }
+
+        let previous = i.checked_sub(1).map(|p| lines[p].trim());
+        let titled = previous.is_some_and(|p| !p.is_empty() && !underline(p, "=-~^\"'`*+#:."));
+        let is_heading = match language {
+            "markdown" => {
+                let level = trimmed.chars().take_while(|c| *c == '#').count();
+                if (1..=6).contains(&level) && trimmed[level..].starts_with([' ', '\t']) {
+                    found.push(i);
+                }
+                titled && underline(line, "=-") && !previous.unwrap().starts_with(['-', '*', '#'])
+            }
+            "asciidoc" => {
+                let level = line.chars().take_while(|c| *c == '=').count();
+                if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+                    found.push(i);
+                }
+                false
+            }
+            "rst" => {
+                titled
+                    && underline(line, "=-~^\"'`*+#:.")
+                    && line.trim_end().len() >= previous.unwrap().len()
+            }
+            _ => false,
+        };
+        if is_heading {
+            found.push(i - 1);
+        }
+    }
+
+    found
+}
+
+/// `changed_region` compares two versions of a document and returns the lines
+/// `[start, end)` of `new` that need linting again, which are the changed lines
+/// widened to the sections (starting at `boundaries`) that contain them, along
+/// with the change in the document's line count.
+pub(crate) fn changed_region(
+    old: &str,
+    new: &str,
+    boundaries: &[usize],
+) -> Option<(usize, usize, isize)> {
+    if old == new {
+        return None;
+    }
+
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take(a.len().min(b.len()) - prefix)
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let start = boundaries
+        .iter()
+        .rev()
+        .copied()
+        .find(|h| *h <= prefix)
+        .unwrap_or(0);
+    let end = boundaries
+        .iter()
+        .copied()
+        .find(|h| *h > start && *h >= b.len() - suffix)
+        .unwrap_or(b.len());
+
+    Some((start, end, b.len() as isize - a.len() as isize))
+}
+
+/// `shift_diagnostics` keeps the diagnostics outside the lines `[start, end)`,
+/// moving those after it by `delta` lines.
+///
+/// The alert in each moved diagnostic's `data` is moved too, since that's
+/// where quick fixes get their position from.
+pub(crate) fn shift_diagnostics(
+    diagnostics: Vec<Diagnostic>,
+    start: usize,
+    end: usize,
+    delta: isize,
+) -> Vec<Diagnostic> {
+    let shift = |p: Position| Position::new((p.line as isize + delta) as u32, p.character);
+    diagnostics
+        .into_iter()
+        .filter_map(|mut d| {
+            if (d.range.end.line as usize) < start {
+                Some(d)
+            } else if d.range.start.line as usize >= end {
+                d.range = Range::new(shift(d.range.start), shift(d.range.end));
+                if let Some(data) = d.data.as_mut() {
+                    if let Ok(mut alert) = serde_json::from_value::<vale::ValeAlert>(data.clone()) {
+                        alert.line = (alert.line as isize + delta) as usize;
+                        *data = serde_json::to_value(alert).unwrap();
+                    }
+                }
+                Some(d)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// The ignore files and `exclude` globs read by `is_ignored`, by root, so
+/// that checking a document doesn't go back to disk every time.
+///
+/// The server clears it whenever the file watcher reports a change.
+#[derive(Debug, Default)]
+pub struct Ignores {
+    roots: DashMap<PathBuf, RootIgnores>,
+}
+
+#[derive(Debug, Default)]
+struct RootIgnores {
+    /// The `exclude` globs, along with the matcher built from them.
+    excludes: Option<(Vec<String>, Gitignore)>,
+    /// The matchers of the `.valeignore` and `.gitignore` files, by path.
+    files: HashMap<PathBuf, Gitignore>,
+}
+
+impl Ignores {
+    /// `is_ignored` reports whether `path` is excluded from linting, either
+    /// by a `.gitignore` or `.valeignore` between it and `root` or by one of
+    /// the `exclude` globs (which are relative to `root`).
+    pub(crate) fn is_ignored(&self, root: &Path, path: &Path, exclude: &[String]) -> bool {
+        if !path.starts_with(root) {
+            return false;
+        }
+        let mut cached = self.roots.entry(root.to_path_buf()).or_default();
+
+        let fresh = cached
+            .excludes
+            .as_ref()
+            .is_some_and(|(globs, _)| globs.as_slice() == exclude);
+        if !fresh {
+            let mut builder = GitignoreBuilder::new(root);
+            for glob in exclude {
+                let _ = builder.add_line(None, glob);
+            }
+            let matcher = builder.build().unwrap_or_else(|_| Gitignore::empty());
+            cached.excludes = Some((exclude.to_vec(), matcher));
+        }
+        if let Some((_, excludes)) = &cached.excludes {
+            if excludes
+                .matched_path_or_any_parents(path, false)
+                .is_ignore()
+            {
+                return true;
+            }
+        }
+
+        // The deepest ignore file with an opinion about `path` wins.
+        for dir in path.ancestors().skip(1) {
+            for name in [".valeignore", ".gitignore"] {
+                let file = dir.join(name);
+                let matcher = cached
+                    .files
+                    .entry(file.clone())
+                    .or_insert_with(|| Gitignore::new(file).0);
+                let matched = matcher.matched_path_or_any_parents(path, false);
+                if matched.is_ignore() {
+                    return true;
+                } else if matched.is_whitelist() {
+                    return false;
+                }
+            }
+            if dir == root {
+                break;
+            }
+        }
+
+        false
+    }
+
+    /// `clear` forgets everything read so far, after ignore files may have
+    /// changed.
+    pub(crate) fn clear(&self) {
+        self.roots.clear();
+    }
+}
+
+/// Extensions of the formats Vale can lint.
+const PROSE_EXTENSIONS: [&str; 14] = [
+    "md", "mdx", "markdown", "rst", "adoc", "asciidoc", "txt", "html", "htm", "org", "tex", "xml",
+    "dita", "rmd",
+];
+
+/// `workspace_extensions` returns the prose file extensions found in `root`,
+/// looking at most `depth` directories deep and skipping hidden and vendored
+/// directories.
+pub(crate) fn workspace_extensions(root: &Path, depth: usize) -> Vec<String> {
+    let mut found = BTreeSet::new();
+    collect_extensions(root, depth, &mut found);
+    found.into_iter().collect()
+}
+
+fn collect_extensions(dir: &Path, depth: usize, found: &mut BTreeSet<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            let skip = name.starts_with('.')
+                || ["node_modules", "target", "vendor"].contains(&name.as_str());
+            if depth > 0 && !skip {
+                collect_extensions(&path, depth - 1, found);
+            }
+        } else if let Some(ext) = path.extension() {
+            let ext = ext.to_string_lossy().to_lowercase();
+            if PROSE_EXTENSIONS.contains(&ext.as_str()) {
+                found.insert(ext);
+            }
+        }
+    }
+}
+
+/// `vale_arch` returns the platform and architecture of the Vale release asset
+/// for this machine, if there is one.
+pub(crate) fn vale_arch() -> Option<String> {
+    release_arch(env::consts::OS, env::consts::ARCH)
+}
+
+/// `release_arch` maps a Rust target OS and architecture to the naming used
+/// by Vale's release assets (e.g., `Linux_arm64`).
+fn release_arch(os: &str, arch: &str) -> Option<String> {
+    let platform = match os {
         "windows" => "Windows",
         "macos" => "macOS",
-        _ => "Linux",
+        "linux" => "Linux",
+        _ => return None,
     };
-    let arch = match env::consts::ARCH {
-        "x86_64" => "64-bit",
-        "arm" => "arm64",
-        "aarch64" => "arm64",
-        _ => "386",
+    let arch = match (os, arch) {
+        (_, "x86_64") => "64-bit",
+        // Deliberate fallback: Vale doesn't publish a native build for
+        // Windows on ARM, so we use the x64 one, which Windows 11 runs under
+        // emulation.
+        ("windows", "aarch64") => "64-bit",
+        (_, "aarch64") => "arm64",
+        ("windows", "x86") | ("linux", "x86") => "386",
+        // There are no 32-bit ARM builds: an `arm64` binary won't run there.
+        _ => return None,
     };
-    format!("{}_{}", platform, arch)
+    Some(format!("{}_{}", platform, arch))
+}
+
+/// `is_musl` reports whether Vale's release binaries, which link against
+/// glibc, can't run here: either we were built for musl, or the system has
+/// musl's dynamic loader and no glibc one (e.g., Alpine without `gcompat`).
+/// The filesystem is only probed once.
+pub(crate) fn is_musl() -> bool {
+    static MUSL: OnceLock<bool> = OnceLock::new();
+    *MUSL.get_or_init(|| {
+        if cfg!(target_env = "musl") {
+            return true;
+        } else if env::consts::OS != "linux" {
+            return false;
+        }
+        has_musl() && !has_glibc()
+    })
+}
+
+/// `has_musl` reports whether musl's dynamic loader is present in `/lib`.
+fn has_musl() -> bool {
+    fs::read_dir("/lib")
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.file_name().to_string_lossy().starts_with("ld-musl-"))
+        })
+        .unwrap_or(false)
+}
+
+/// `has_glibc` reports whether glibc's dynamic loader is present, which is
+/// the case on musl systems with a compatibility layer such as `gcompat`.
+fn has_glibc() -> bool {
+    ["/lib64/ld-linux-x86-64.so.2", "/lib/ld-linux-aarch64.so.1"]
+        .iter()
+        .any(|p| Path::new(p).exists())
 }
 
 pub(crate) fn position_to_range(p: Position, rope: &Rope) -> Option<Range> {
@@ -74,24 +566,138 @@ pub(crate) fn range_to_token(r: Range, rope: &Rope) -> String {
     token.to_string()
 }
 
-pub(crate) fn alert_to_range(alert: vale::ValeAlert) -> Range {
+/// `negotiate_encoding` picks the position encoding to use with the client.
+///
+/// Vale reports byte offsets, so UTF-8 is preferred, followed by UTF-32; the
+/// LSP default, UTF-16, is used otherwise. The older `offsetEncoding`
+/// extension is honored for clients that don't send `positionEncodings`.
+pub(crate) fn negotiate_encoding(caps: &ClientCapabilities) -> PositionEncodingKind {
+    let mut offered: Vec<PositionEncodingKind> = caps
+        .general
+        .as_ref()
+        .and_then(|g| g.position_encodings.clone())
+        .unwrap_or_default();
+    if offered.is_empty() {
+        offered = caps
+            .offset_encoding
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(PositionEncodingKind::from)
+            .collect();
+    }
+
+    [PositionEncodingKind::UTF8, PositionEncodingKind::UTF32]
+        .into_iter()
+        .find(|e| offered.contains(e))
+        .unwrap_or(PositionEncodingKind::UTF16)
+}
+
+/// `char_to_column` converts a char index into `line` to a column in the
+/// client's position encoding.
+pub(crate) fn char_to_column(line: RopeSlice, idx: usize, encoding: &PositionEncodingKind) -> u32 {
+    let idx = idx.min(line.len_chars());
+    let column = if *encoding == PositionEncodingKind::UTF8 {
+        line.char_to_byte(idx)
+    } else if *encoding == PositionEncodingKind::UTF32 {
+        idx
+    } else {
+        line.char_to_utf16_cu(idx)
+    };
+    column as u32
+}
+
+/// `span_to_range` converts one of Vale's spans (1-based, inclusive byte
+/// offsets into the 0-based `line`) into a `Range` in the client's position
+/// encoding.
+///
+/// Offsets that fall inside a multi-byte character are widened to cover the
+/// whole character.
+pub(crate) fn span_to_range(
+    rope: &Rope,
+    line: usize,
+    span: (usize, usize),
+    encoding: &PositionEncodingKind,
+) -> Range {
+    let context = rope.line(line);
+    let len = context.len_bytes();
+
+    let start = context.byte_to_char(span.0.saturating_sub(1).min(len));
+    let end = match span.1.min(len) {
+        0 => 0,
+        b => context.byte_to_char(b - 1) + 1,
+    };
+    // Offsets past the end of the line are passed through unchanged.
+    let overflow = span.1.saturating_sub(len) as u32;
+
     Range {
-        start: Position {
-            line: alert.line as u32 - 1,
-            character: alert.span.0 as u32 - 1,
-        },
-        end: Position {
-            line: alert.line as u32 - 1,
-            character: alert.span.1 as u32,
+        start: Position::new(line as u32, char_to_column(context, start, encoding)),
+        end: Position::new(
+            line as u32,
+            char_to_column(context, end, encoding) + overflow,
+        ),
+    }
+}
+
+/// `alert_to_range` converts the alert's span into a `Range` in the client's
+/// position encoding.
+///
+/// Vale's spans are byte offsets, so the document's text is needed to
+/// convert them; without it, they're used as-is.
+pub(crate) fn alert_to_range(
+    alert: &vale::ValeAlert,
+    rope: Option<&Rope>,
+    encoding: &PositionEncodingKind,
+) -> Range {
+    let line = alert.line - 1;
+    match rope {
+        Some(rope) if line < rope.len_lines() => span_to_range(rope, line, alert.span, encoding),
+        _ => Range {
+            start: Position::new(line as u32, alert.span.0 as u32 - 1),
+            end: Position::new(line as u32, alert.span.1 as u32),
         },
     }
 }
 
-pub(crate) fn severity_to_level(severity: String) -> DiagnosticSeverity {
-    match severity.as_str() {
+/// `alert_to_edit` builds the `TextEdit` that applies `fix` to the alert's
+/// range.
+pub(crate) fn alert_to_edit(
+    alert: &vale::ValeAlert,
+    fix: String,
+    rope: Option<&Rope>,
+    encoding: &PositionEncodingKind,
+) -> TextEdit {
+    let mut range = alert_to_range(alert, rope, encoding);
+    if alert.action.name.as_deref() == Some("remove") {
+        // NOTE: we need to add a character when deleting to avoid
+        // leaving a double space.
+        range.end.character += 1;
+    }
+    TextEdit {
+        range,
+        new_text: fix,
+    }
+}
+
+/// `severity_to_level` maps a Vale severity to an LSP one.
+///
+/// `overrides` is the `severities` setting, an object mapping Vale severities
+/// to "error", "warning", "information", or "hint".
+pub(crate) fn severity_to_level(severity: &str, overrides: Option<&Value>) -> DiagnosticSeverity {
+    let level = overrides
+        .and_then(|o| o.get(severity))
+        .and_then(|l| l.as_str())
+        .unwrap_or(match severity {
+            "error" => "error",
+            "warning" => "warning",
+            "suggestion" => "information",
+            _ => "hint",
+        });
+
+    match level {
         "error" => DiagnosticSeverity::ERROR,
         "warning" => DiagnosticSeverity::WARNING,
-        "suggestion" => DiagnosticSeverity::INFORMATION,
+        "information" | "info" => DiagnosticSeverity::INFORMATION,
         _ => DiagnosticSeverity::HINT,
     }
 }
@@ -125,10 +731,15 @@ pub(crate) fn pkg_to_completion(pkg: pkg::Package) -> CompletionItem {
     }
 }
 
-pub(crate) fn alert_to_diagnostic(alert: &vale::ValeAlert) -> Diagnostic {
+pub(crate) fn alert_to_diagnostic(
+    alert: &vale::ValeAlert,
+    rope: Option<&Rope>,
+    encoding: &PositionEncodingKind,
+    severities: Option<&Value>,
+) -> Diagnostic {
     let mut d = Diagnostic {
-        range: alert_to_range(alert.clone()),
-        severity: Some(severity_to_level(alert.severity.clone())),
+        range: alert_to_range(alert, rope, encoding),
+        severity: Some(severity_to_level(&alert.severity, severities)),
         code: Some(NumberOrString::String(alert.check.clone())),
         source: Some("vale-ls".to_string()),
         message: alert.message.clone(),
@@ -138,6 +749,11 @@ pub(crate) fn alert_to_diagnostic(alert: &vale::ValeAlert) -> Diagnostic {
         data: Some(serde_json::to_value(alert).unwrap()),
     };
 
+    // Editors render unnecessary code as faded, which suits redundant words.
+    if alert.action.name.as_deref() == Some("remove") {
+        d.tags = Some(vec![DiagnosticTag::UNNECESSARY]);
+    }
+
     if alert.link != "" {
         let uri = Url::from_str(&alert.link);
         if uri.is_ok() {
@@ -154,13 +770,277 @@ pub(crate) fn alert_to_diagnostic(alert: &vale::ValeAlert) -> Diagnostic {
 mod tests {
     use super::*;
 
+    #[test]
+    fn section_headings() {
+        let md = "# Title\n\nText\n\n```\n# not a heading\n```\n\nSetext\n------\n\n## Next\n";
+        assert_eq!(headings(md, "markdown"), vec![0, 8, 11]);
+
+        let rst = "=====\nTitle\n=====\n\nText\n\nSection\n-------\n";
+        assert_eq!(headings(rst, "rst"), vec![1, 6]);
+
+        let adoc = "= Title\n\n----\n== not a heading\n----\n\n== Section\n";
+        assert_eq!(headings(adoc, "asciidoc"), vec![0, 6]);
+
+        assert!(headings(md, "text").is_empty());
+    }
+
+    #[test]
+    fn regions() {
+        let old = "# A\n\none\n\n# B\n\ntwo\n\n# C\n\nthree\n";
+        assert_eq!(changed_region(old, old, &headings(old, "markdown")), None);
+
+        let new = old.replace("two", "two\nmore");
+        assert_eq!(
+            changed_region(old, &new, &headings(&new, "markdown")),
+            Some((4, 9, 1))
+        );
+
+        let new = old.replace("three\n", "");
+        assert_eq!(
+            changed_region(old, &new, &headings(&new, "markdown")),
+            Some((8, 10, -1))
+        );
+
+        let new = old.replace("one", "uno");
+        assert_eq!(changed_region(old, &new, &[]), Some((0, 11, 0)));
+
+        let at = |line: u32| Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, 3)),
+            ..Diagnostic::default()
+        };
+        let kept = shift_diagnostics(vec![at(2), at(6), at(10)], 4, 8, 1);
+        let lines: Vec<u32> = kept.iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![2, 11]);
+    }
+
+    #[test]
+    fn shifted_fixes() {
+        let alert: vale::ValeAlert = serde_json::from_value(serde_json::json!({
+            "Action": {"Name": "replace", "Params": ["use"]},
+            "Check": "Test.Rule",
+            "Match": "utilize",
+            "Description": "",
+            "Link": "",
+            "Line": 3,
+            "Span": [4, 10],
+            "Severity": "warning",
+            "Message": "Use 'use'."
+        }))
+        .unwrap();
+
+        let encoding = PositionEncodingKind::UTF16;
+        let old = Rope::from_str("# A\n\nWe utilize it.\n");
+        let d = alert_to_diagnostic(&alert, Some(&old), &encoding, None);
+
+        let new = Rope::from_str("# A\n\nMore.\n\nWe utilize it.\n");
+        let d = shift_diagnostics(vec![d], 1, 2, 2).remove(0);
+        assert_eq!(d.range.start.line, 4);
+
+        let shifted: vale::ValeAlert = serde_json::from_value(d.data.unwrap()).unwrap();
+        let edit = alert_to_edit(&shifted, "use".to_string(), Some(&new), &encoding);
+        assert_eq!(edit.range, d.range);
+    }
+
+    #[test]
+    fn ignored() {
+        let ignores = Ignores::default();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("docs/build")).unwrap();
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        fs::write(root.join("docs/.valeignore"), "draft-*.md\n").unwrap();
+
+        assert!(ignores.is_ignored(root, &root.join("docs/build/index.md"), &[]));
+        assert!(ignores.is_ignored(root, &root.join("docs/draft-1.md"), &[]));
+        assert!(!ignores.is_ignored(root, &root.join("docs/index.md"), &[]));
+        assert!(ignores.is_ignored(
+            root,
+            &root.join("docs/index.md"),
+            &["docs/*.md".to_string()]
+        ));
+
+        // Ignore files are cached until they're cleared.
+        fs::write(root.join("docs/.valeignore"), "index.md\n").unwrap();
+        assert!(!ignores.is_ignored(root, &root.join("docs/index.md"), &[]));
+        ignores.clear();
+        assert!(ignores.is_ignored(root, &root.join("docs/index.md"), &[]));
+    }
+
+    #[test]
+    fn windows_paths() {
+        let simplify = |p: &str| normalize_path(Path::new(p)).display().to_string();
+        assert_eq!(simplify(r"\\?\C:\docs\a.md"), r"C:\docs\a.md");
+        assert_eq!(
+            simplify(r"\\?\UNC\server\share\a.md"),
+            r"\\server\share\a.md"
+        );
+        assert_eq!(
+            simplify(r"\\?\C:\docs\trailing.\a.md"),
+            r"\\?\C:\docs\trailing.\a.md"
+        );
+        assert_eq!(simplify(r"\\?\Volume{1234}\a.md"), r"\\?\Volume{1234}\a.md");
+        assert_eq!(simplify("/docs/a.md"), "/docs/a.md");
+    }
+
+    #[test]
+    fn language_ids() {
+        assert_eq!(language_id("/docs/index.md"), "markdown");
+        assert_eq!(language_id("/docs/guide.adoc"), "asciidoc");
+        assert_eq!(language_id("/repo/.git/COMMIT_EDITMSG"), "gitcommit");
+        assert_eq!(language_id("/src/main.rs"), "");
+    }
+
+    #[test]
+    fn language_exts() {
+        assert_eq!(language_ext("markdown"), Some("md"));
+        assert_eq!(language_ext("plaintext"), Some("txt"));
+        assert_eq!(language_ext("rust"), None);
+    }
+
+    #[test]
+    fn severities() {
+        assert_eq!(
+            severity_to_level("suggestion", None),
+            DiagnosticSeverity::INFORMATION
+        );
+
+        let overrides = serde_json::json!({ "suggestion": "hint", "warning": "error" });
+        assert_eq!(
+            severity_to_level("suggestion", Some(&overrides)),
+            DiagnosticSeverity::HINT
+        );
+        assert_eq!(
+            severity_to_level("warning", Some(&overrides)),
+            DiagnosticSeverity::ERROR
+        );
+        assert_eq!(
+            severity_to_level("error", Some(&overrides)),
+            DiagnosticSeverity::ERROR
+        );
+    }
+
+    #[test]
+    fn encodings() {
+        let mut caps = ClientCapabilities::default();
+        assert_eq!(negotiate_encoding(&caps), PositionEncodingKind::UTF16);
+
+        caps.offset_encoding = Some(vec!["utf-8".to_string()]);
+        assert_eq!(negotiate_encoding(&caps), PositionEncodingKind::UTF8);
+
+        caps.general = Some(GeneralClientCapabilities {
+            position_encodings: Some(vec![
+                PositionEncodingKind::UTF16,
+                PositionEncodingKind::UTF32,
+            ]),
+            ..GeneralClientCapabilities::default()
+        });
+        assert_eq!(negotiate_encoding(&caps), PositionEncodingKind::UTF32);
+
+        // 'ï' and 'é' are two bytes each; "café" is bytes 8 through 12.
+        let rope = Rope::from_str("naïve café\n");
+        let range = span_to_range(&rope, 0, (8, 12), &PositionEncodingKind::UTF16);
+        assert_eq!((range.start.character, range.end.character), (6, 10));
+        let range = span_to_range(&rope, 0, (8, 12), &PositionEncodingKind::UTF8);
+        assert_eq!((range.start.character, range.end.character), (7, 12));
+
+        // '😀' is four bytes and two UTF-16 code units; "b" is byte 8.
+        let rope = Rope::from_str("a 😀 b");
+        let range = span_to_range(&rope, 0, (8, 8), &PositionEncodingKind::UTF16);
+        assert_eq!((range.start.character, range.end.character), (5, 6));
+        let range = span_to_range(&rope, 0, (8, 8), &PositionEncodingKind::UTF32);
+        assert_eq!((range.start.character, range.end.character), (4, 5));
+
+        // An end offset inside a multi-byte character covers all of it.
+        let rope = Rope::from_str("一二三");
+        let range = span_to_range(&rope, 0, (4, 4), &PositionEncodingKind::UTF16);
+        assert_eq!((range.start.character, range.end.character), (1, 2));
+    }
+
+    #[test]
+    fn unnecessary() {
+        let alert = |action: &str| -> vale::ValeAlert {
+            serde_json::from_value(serde_json::json!({
+                "Action": {"Name": action, "Params": null},
+                "Check": "Test.Rule",
+                "Match": "very",
+                "Description": "",
+                "Link": "",
+                "Line": 1,
+                "Span": [1, 4],
+                "Severity": "warning",
+                "Message": "Avoid 'very'."
+            }))
+            .unwrap()
+        };
+
+        let encoding = PositionEncodingKind::UTF16;
+        let removed = alert_to_diagnostic(&alert("remove"), None, &encoding, None);
+        assert_eq!(removed.tags, Some(vec![DiagnosticTag::UNNECESSARY]));
+        let replaced = alert_to_diagnostic(&alert("replace"), None, &encoding, None);
+        assert_eq!(replaced.tags, None);
+    }
+
+    #[test]
+    fn vocab() {
+        assert_eq!(vocab_lists("Vale.Spelling"), vec!["accept"]);
+        assert_eq!(vocab_lists("Vale.Terms"), vec!["accept", "reject"]);
+        assert_eq!(vocab_lists("Vale.Avoid"), vec!["accept", "reject"]);
+        assert!(vocab_lists("Microsoft.Passive").is_empty());
+    }
+
+    #[test]
+    fn directive() {
+        let (off, on, inline) = comment_directive("md", "Vale.Spelling");
+        assert_eq!(off, "<!-- vale Vale.Spelling = NO -->");
+        assert_eq!(on, "<!-- vale Vale.Spelling = YES -->");
+        assert!(inline);
+
+        let (off, _, inline) = comment_directive("rs", "Vale.Spelling");
+        assert_eq!(off, "// vale Vale.Spelling = NO");
+        assert!(!inline);
+    }
+
+    #[test]
+    fn extensions() {
+        let found = workspace_extensions(Path::new("."), 2);
+        assert!(found.contains(&"md".to_string()));
+        assert!(!found.contains(&"rs".to_string()));
+    }
+
     #[test]
     fn arch() {
-        let arch = vale_arch();
+        let arch = vale_arch().unwrap();
         match env::consts::OS {
             "windows" => assert_eq!(arch, "Windows_64-bit"),
             "macos" => assert!(arch == "macOS_64-bit" || arch == "macOS_arm64"),
             _ => assert_eq!(arch, "Linux_64-bit"),
         }
     }
+
+    #[test]
+    fn release_archs() {
+        let cases = [
+            ("linux", "x86_64", Some("Linux_64-bit")),
+            ("linux", "aarch64", Some("Linux_arm64")),
+            ("linux", "x86", Some("Linux_386")),
+            ("linux", "arm", None),
+            ("macos", "x86_64", Some("macOS_64-bit")),
+            ("macos", "aarch64", Some("macOS_arm64")),
+            ("windows", "x86_64", Some("Windows_64-bit")),
+            // Windows on ARM falls back to the emulated x64 build.
+            ("windows", "aarch64", Some("Windows_64-bit")),
+            ("windows", "x86", Some("Windows_386")),
+            ("windows", "arm", None),
+            ("freebsd", "x86_64", None),
+        ];
+        for (os, arch, expected) in cases {
+            assert_eq!(
+                release_arch(os, arch).as_deref(),
+                expected,
+                "{}/{}",
+                os,
+                arch
+            );
+        }
+    }
 }
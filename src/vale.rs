@@ -1,12 +1,15 @@
 use core::fmt;
 use std::collections::HashMap;
-use std::io::Write;
+use std::io::{Seek, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Output};
-use std::{env, io, path};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use std::{env, io};
 
+use dashmap::DashMap;
 use flate2::read::GzDecoder;
-use reqwest;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use tar::Archive;
@@ -14,16 +17,20 @@ use tempfile::NamedTempFile;
 use which::which;
 
 use crate::error::Error;
+use crate::http;
 use crate::regex101;
-use crate::utils::vale_arch;
+use crate::utils::{is_musl, vale_arch};
 
 const RELEASES: &str = "https://github.com/errata-ai/vale/releases/download";
 const LATEST: &str = "https://api.github.com/repos/errata-ai/vale/releases/latest";
+const ALL_RELEASES: &str = "https://api.github.com/repos/errata-ai/vale/releases";
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub(crate) struct ValeConfig {
     pub styles_path: PathBuf,
+    #[serde(default)]
+    pub vocab: Vec<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -51,9 +58,84 @@ impl fmt::Display for ValeError {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub(crate) struct ValeMetrics {
+    pub characters: f64,
+    pub words: f64,
+    pub sentences: f64,
+    pub paragraphs: f64,
+    pub syllables: f64,
+    pub complex_words: f64,
+    pub long_words: f64,
+    pub polysyllabic_words: f64,
+}
+
+impl ValeMetrics {
+    /// `flesch_reading_ease` computes the Flesch reading-ease score.
+    pub fn flesch_reading_ease(&self) -> f64 {
+        if self.words == 0.0 || self.sentences == 0.0 {
+            return 0.0;
+        }
+        206.835 - 1.015 * (self.words / self.sentences) - 84.6 * (self.syllables / self.words)
+    }
+
+    /// `words_per_sentence` returns the average sentence length.
+    pub fn words_per_sentence(&self) -> f64 {
+        if self.sentences == 0.0 {
+            return 0.0;
+        }
+        self.words / self.sentences
+    }
+
+    /// `flesch_kincaid` computes the Flesch-Kincaid grade level.
+    pub fn flesch_kincaid(&self) -> f64 {
+        if self.words == 0.0 || self.sentences == 0.0 {
+            return 0.0;
+        }
+        0.39 * (self.words / self.sentences) + 11.8 * (self.syllables / self.words) - 15.59
+    }
+
+    /// `gunning_fog` computes the Gunning Fog index.
+    pub fn gunning_fog(&self) -> f64 {
+        if self.words == 0.0 || self.sentences == 0.0 {
+            return 0.0;
+        }
+        0.4 * ((self.words / self.sentences) + 100.0 * (self.complex_words / self.words))
+    }
+
+    /// `smog` computes the SMOG grade.
+    pub fn smog(&self) -> f64 {
+        if self.sentences == 0.0 {
+            return 0.0;
+        }
+        1.0430 * (self.polysyllabic_words * 30.0 / self.sentences).sqrt() + 3.1291
+    }
+
+    /// `coleman_liau` computes the Coleman-Liau index.
+    pub fn coleman_liau(&self) -> f64 {
+        if self.words == 0.0 {
+            return 0.0;
+        }
+        let l = self.characters / self.words * 100.0;
+        let s = self.sentences / self.words * 100.0;
+        0.0588 * l - 0.296 * s - 15.8
+    }
+
+    /// `automated_readability` computes the Automated Readability Index.
+    pub fn automated_readability(&self) -> f64 {
+        if self.words == 0.0 || self.sentences == 0.0 {
+            return 0.0;
+        }
+        4.71 * (self.characters / self.words) + 0.5 * (self.words / self.sentences) - 21.43
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub(crate) struct Release {
     tag_name: String,
+    #[serde(default)]
+    draft: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -94,13 +176,30 @@ pub(crate) struct ValeAlert {
 
 #[derive(Debug, Clone)]
 pub struct ValeManager {
-    pub managed_exe: PathBuf,
-    pub managed_bin: PathBuf,
+    /// The directory we install Vale into (see `set_binary_dir`).
+    pub managed_bin: Arc<RwLock<PathBuf>>,
+    /// Where older versions of vale-ls installed Vale: next to our own
+    /// executable.
+    pub legacy_bin: PathBuf,
+    pub exe_name: String,
 
     pub args: Vec<String>,
-    pub arch: String,
+    /// The release asset for this machine, if Vale publishes one.
+    pub arch: Option<String>,
+    /// Whether we're on a musl-based system, which Vale doesn't publish
+    /// binaries for.
+    pub musl: bool,
+    /// The release asset to install, overriding `arch` (see `set_arch`).
+    pub custom_arch: Arc<RwLock<Option<String>>>,
 
     pub fallback_exe: PathBuf,
+    pub custom_exe: Arc<RwLock<Option<PathBuf>>>,
+    pub extra_args: Arc<RwLock<Vec<String>>>,
+    /// Whether to install release candidates as well as stable releases.
+    pub prerelease: Arc<AtomicBool>,
+
+    /// The results of `ls-config`, by config path and working directory.
+    config_cache: Arc<DashMap<(String, String), ValeConfig>>,
 }
 
 // ValeManager manages the installation and execution of Vale.
@@ -116,37 +215,174 @@ impl ValeManager {
         let arch = vale_arch();
 
         let fallback = which("vale").unwrap_or(PathBuf::from(""));
-        let mut bin_dir = match env::current_exe() {
-            Ok(exe_path) => exe_path.parent().unwrap().to_path_buf(),
-            Err(_) => PathBuf::from(""),
+        let legacy_bin = match env::current_exe() {
+            Ok(exe_path) => exe_path.parent().unwrap().join("vale_bin"),
+            Err(_) => PathBuf::from("vale_bin"),
         };
 
         let mut exe = "vale".to_string();
-        if arch.to_lowercase().contains("windows") {
+        if env::consts::OS == "windows" {
             exe += ".exe";
         }
 
-        bin_dir.push(path::Path::new("vale_bin"));
         ValeManager {
-            managed_bin: bin_dir.clone(),
-            managed_exe: bin_dir.join(path::Path::new(&exe)),
+            managed_bin: Arc::new(RwLock::new(default_bin_dir(&legacy_bin))),
+            legacy_bin,
+            exe_name: exe,
             args: vec!["--output=JSON".to_string()],
             arch,
+            musl: is_musl(),
+            custom_arch: Arc::new(RwLock::new(None)),
             fallback_exe: fallback,
+            custom_exe: Arc::new(RwLock::new(None)),
+            extra_args: Arc::new(RwLock::new(vec![])),
+            prerelease: Arc::new(AtomicBool::new(false)),
+            config_cache: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// `set_custom_exe` points the manager at a user-provided executable,
+    /// which takes priority over both the managed and system versions.
+    ///
+    /// `exe` may be a path or a command on the `PATH`; an empty string clears
+    /// it.
+    pub(crate) fn set_custom_exe(&self, exe: &str) -> Result<(), Error> {
+        let custom = match exe {
+            "" => None,
+            exe => Some(which(exe).map_err(|_| Error::from(format!("'{}' not found.", exe)))?),
+        };
+        *self.custom_exe.write().unwrap() = custom;
+        self.invalidate();
+        Ok(())
+    }
+
+    /// `install_to` installs the given version of Vale (or the latest one)
+    /// into `dir`, defaulting to the managed location.
+    pub async fn install_to(
+        &self,
+        version: Option<&str>,
+        dir: Option<&Path>,
+    ) -> Result<String, Error> {
+        let v = match version {
+            Some(v) => v.trim_start_matches('v').to_string(),
+            None => self.fetch_version().await?,
+        };
+        let dir = match dir {
+            Some(dir) => dir.to_path_buf(),
+            None => self.managed_bin(),
+        };
+
+        self.install(&dir, &v, &self.asset_arch()?).await?;
+        Ok(format!("Vale v{} installed to {}.", v, dir.display()))
+    }
+
+    /// `uninstall` removes the managed installation of Vale, if there is one.
+    ///
+    /// Since `binaryDir` may point at a directory shared with other tools, we
+    /// only remove the files we installed, and then the directory itself if
+    /// nothing else is left in it.
+    pub(crate) fn uninstall(&self) -> Result<bool, Error> {
+        let mut removed = false;
+        for dir in [self.managed_bin(), self.legacy_bin.clone()] {
+            let exe = dir.join(&self.exe_name);
+            if !exe.exists() {
+                continue;
+            }
+            std::fs::remove_file(&exe)?;
+            for extra in ["LICENSE", "README.md"] {
+                let _ = std::fs::remove_file(dir.join(extra));
+            }
+            let _ = std::fs::remove_dir(&dir);
+            removed = true;
         }
+        self.invalidate();
+        Ok(removed)
+    }
+
+    /// `set_arch` overrides the platform and architecture of the release asset
+    /// we install (e.g., `Linux_arm64`), or restores auto-detection if `arch`
+    /// is empty.
+    pub fn set_arch(&self, arch: &str) {
+        *self.custom_arch.write().unwrap() = match arch {
+            "" => None,
+            arch => Some(arch.to_string()),
+        };
+    }
+
+    /// `asset_arch` returns the platform and architecture of the release asset
+    /// to install.
+    fn asset_arch(&self) -> Result<String, Error> {
+        if let Some(arch) = self.custom_arch.read().unwrap().as_ref() {
+            return Ok(arch.clone());
+        } else if self.musl {
+            return Err(Error::from(
+                "Vale's Linux releases require glibc, which this system doesn't appear to have. \
+                 Install Vale with your package manager (e.g., `apk add vale`) and set `valePath`, \
+                 or set `arch` to choose a release anyway.",
+            ));
+        }
+        self.arch.clone().ok_or(Error::from(format!(
+            "Vale doesn't publish binaries for {} on {}; install it yourself and set \
+             `valePath`, or set `arch` to choose a release anyway.",
+            env::consts::OS,
+            env::consts::ARCH
+        )))
+    }
+
+    /// `managed_bin` returns the directory we install Vale into.
+    pub fn managed_bin(&self) -> PathBuf {
+        self.managed_bin.read().unwrap().clone()
+    }
+
+    /// `set_binary_dir` sets the directory we install Vale into, or restores
+    /// the default (see `default_bin_dir`) if `dir` is empty.
+    pub(crate) fn set_binary_dir(&self, dir: &str) {
+        let dir = match dir {
+            "" => default_bin_dir(&self.legacy_bin),
+            dir => PathBuf::from(dir),
+        };
+        *self.managed_bin.write().unwrap() = dir;
+        self.invalidate();
+    }
+
+    /// `set_extra_args` sets arguments to pass to Vale, after our own, when
+    /// linting.
+    pub(crate) fn set_extra_args(&self, args: Vec<String>) {
+        *self.extra_args.write().unwrap() = args;
+    }
+
+    /// `set_prerelease` chooses between the stable and prerelease update
+    /// channels.
+    pub(crate) fn set_prerelease(&self, prerelease: bool) {
+        self.prerelease.store(prerelease, Ordering::Relaxed);
+    }
+
+    /// `lint_args` returns the arguments every lint run starts with.
+    fn lint_args(&self) -> Vec<String> {
+        let mut args = self.args.clone();
+        args.extend(self.extra_args.read().unwrap().iter().cloned());
+        args
     }
 
     pub(crate) fn is_installed(&self) -> bool {
-        self.managed_exe.exists() || self.fallback_exe.exists()
+        self.exe_path(false).is_ok()
     }
 
     /// `install_or_update` checks if Vale is installed and, if so, checks if it's
     /// the latest version.
-    pub(crate) fn install_or_update(&self) -> Result<String, Error> {
-        let newer = self.newer_version()?;
+    pub(crate) async fn install_or_update(&self) -> Result<String, Error> {
+        let arch = match self.asset_arch() {
+            Ok(arch) => arch,
+            // There's nothing for us to install, but the user has already
+            // installed Vale themselves.
+            Err(_) if self.is_installed() => return Ok("Using the system's Vale.".to_string()),
+            Err(err) => return Err(err),
+        };
+
+        let newer = self.newer_version().await?;
         if newer.is_some() {
             let v = newer.unwrap();
-            self.install(&self.managed_bin, &v, &self.arch)?;
+            self.install(&self.managed_bin(), &v, &arch).await?;
             Ok(format!("Vale v{} installed.", v))
         } else {
             Ok("Vale is up to date.".to_string())
@@ -162,7 +398,7 @@ impl ValeManager {
         config_path: String,
         filter: String,
     ) -> Result<HashMap<String, Vec<ValeAlert>>, Error> {
-        let mut args = self.args.clone();
+        let mut args = self.lint_args();
         let cwd = fp.parent().unwrap();
 
         if config_path != "" {
@@ -174,23 +410,97 @@ impl ValeManager {
         args.push(fp.as_path().display().to_string());
 
         let exe = self.exe_path(false)?;
-        let out = Command::new(exe.as_os_str())
-            .current_dir(cwd)
-            .args(args)
-            .output()?;
+        let out = output(Command::new(exe.as_os_str()).current_dir(cwd).args(args))?;
 
         self.parse_output(out)
     }
 
+    /// `run_dir` executes Vale on every file in `dir` matched by the active
+    /// configuration.
+    ///
+    /// The returned map is keyed by absolute file paths.
+    pub(crate) fn run_dir(
+        &self,
+        dir: PathBuf,
+        config_path: String,
+        filter: String,
+    ) -> Result<HashMap<String, Vec<ValeAlert>>, Error> {
+        let mut args = self.lint_args();
+
+        if !config_path.is_empty() {
+            args.push(format!("--config={}", config_path));
+        }
+        if !filter.is_empty() {
+            args.push(format!("--filter={}", filter));
+        }
+        args.push(dir.as_path().display().to_string());
+
+        let exe = self.exe_path(false)?;
+        let out = output(
+            Command::new(exe.as_os_str())
+                .current_dir(dir.clone())
+                .args(args),
+        )?;
+
+        let results = self.parse_output(out)?;
+        Ok(results
+            .into_iter()
+            .map(|(k, v)| (dir.join(k).display().to_string(), v))
+            .collect())
+    }
+
+    /// `run_stdin` executes Vale on the given text rather than a file on disk.
+    ///
+    /// The text is passed through stdin and `--ext` is set from `fp` so that
+    /// Vale can still pick the right format and config section.
+    pub(crate) fn run_stdin(
+        &self,
+        fp: PathBuf,
+        text: &str,
+        config_path: String,
+        filter: String,
+    ) -> Result<HashMap<String, Vec<ValeAlert>>, Error> {
+        let mut args = self.lint_args();
+        let cwd = fp.parent().unwrap();
+
+        if !config_path.is_empty() {
+            args.push(format!("--config={}", config_path));
+        }
+        if !filter.is_empty() {
+            args.push(format!("--filter={}", filter));
+        }
+        if let Some(ext) = fp.extension() {
+            args.push(format!("--ext=.{}", ext.to_string_lossy()));
+        }
+
+        let exe = self.exe_path(false)?;
+        let mut cmd = Command::new(exe.as_os_str());
+        cmd.current_dir(cwd)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let start = Instant::now();
+        let mut child = cmd.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+
+        let result = child.wait_with_output();
+        log_run(&cmd, start, &result);
+        self.parse_output(result?)
+    }
+
     pub(crate) fn version(&self, managed: bool) -> Result<String, Error> {
         let exe = self.exe_path(managed)?;
-        let out = Command::new(exe.as_os_str()).arg("-v").output()?;
+        let out = output(Command::new(exe.as_os_str()).arg("-v"))?;
         let buf = String::from_utf8(out.stdout)?;
 
         let v = buf
             .trim()
             .strip_prefix("vale version ")
-            .unwrap()
+            .ok_or_else(|| Error::from(format!("Unexpected `vale -v` output: {}", buf.trim())))?
             .to_string();
 
         Ok(v)
@@ -204,16 +514,57 @@ impl ValeManager {
         args.push("sync".to_string());
 
         let exe = self.exe_path(false)?;
-        let _ = Command::new(exe.as_os_str())
-            .current_dir(cwd.clone())
-            .args(args)
-            // NOTE: Calling `status` causes the server to crash?
-            .output()?;
+        // NOTE: Calling `status` causes the server to crash?
+        let _ = output(Command::new(exe.as_os_str()).current_dir(cwd).args(args))?;
 
         Ok(())
     }
 
+    /// `config` returns the configuration Vale resolves for the given config
+    /// path and working directory.
+    ///
+    /// Results are cached until `invalidate` is called.
     pub(crate) fn config(&self, config_path: String, cwd: String) -> Result<ValeConfig, Error> {
+        let key = (config_path, cwd);
+        if let Some(config) = self.config_cache.get(&key) {
+            return Ok(config.clone());
+        }
+
+        let config = self.load_config(&key.0, &key.1)?;
+        self.config_cache.insert(key, config.clone());
+        Ok(config)
+    }
+
+    /// `invalidate` forgets cached configurations, which is needed whenever a
+    /// config file (or the executable) changes.
+    pub(crate) fn invalidate(&self) {
+        self.config_cache.clear();
+    }
+
+    fn load_config(&self, config_path: &str, cwd: &str) -> Result<ValeConfig, Error> {
+        let out = self.ls_config(config_path, cwd)?;
+        let config: ValeConfig = serde_json::from_slice(&out.stdout)?;
+        Ok(config)
+    }
+
+    /// `resolved_config` returns the full configuration `ls-config` reports,
+    /// rather than just the parts we use.
+    pub(crate) fn resolved_config(
+        &self,
+        config_path: &str,
+        cwd: &str,
+    ) -> Result<serde_json::Value, Error> {
+        let out = self.ls_config(config_path, cwd)?;
+        serde_json::from_slice(&out.stdout).map_err(|e| {
+            let stderr = String::from_utf8_lossy(&out.stderr).trim().to_string();
+            match stderr.is_empty() {
+                true => Error::from(e),
+                false => Error::Msg(stderr),
+            }
+        })
+    }
+
+    fn ls_config(&self, config_path: &str, cwd: &str) -> Result<Output, Error> {
         let mut args = vec![];
         if config_path != "" {
             args.push(format!("--config={}", config_path));
@@ -221,13 +572,32 @@ impl ValeManager {
         args.push("ls-config".to_string());
 
         let exe = self.exe_path(false)?;
-        let out = Command::new(exe.as_os_str())
-            .current_dir(cwd.clone())
-            .args(args)
-            .output()?;
+        let out = output(Command::new(exe.as_os_str()).current_dir(cwd).args(args))?;
+        Ok(out)
+    }
 
-        let config: ValeConfig = serde_json::from_slice(&out.stdout)?;
-        Ok(config)
+    /// `metrics` returns the readability metrics Vale computes for a file.
+    pub(crate) fn metrics(&self, fp: PathBuf, config_path: String) -> Result<ValeMetrics, Error> {
+        let mut args = vec![];
+        if !config_path.is_empty() {
+            args.push(format!("--config={}", config_path));
+        }
+        args.push("ls-metrics".to_string());
+        args.push(fp.as_path().display().to_string());
+
+        let exe = self.exe_path(false)?;
+        let out = output(
+            Command::new(exe.as_os_str())
+                .current_dir(fp.parent().unwrap())
+                .args(args),
+        )?;
+
+        if out.stdout.is_empty() {
+            return Err(Error::Msg(String::from_utf8(out.stderr)?));
+        }
+
+        let metrics: ValeMetrics = serde_json::from_slice(&out.stdout)?;
+        Ok(metrics)
     }
 
     pub(crate) fn fix(&self, alert: &str) -> Result<ValeFix, Error> {
@@ -235,28 +605,64 @@ impl ValeManager {
         file.write_all(alert.as_bytes())?;
 
         let exe = self.exe_path(false)?;
-        let out = Command::new(exe.as_os_str())
-            .arg("fix")
-            .arg(file.path())
-            .output()?;
+        let out = output(Command::new(exe.as_os_str()).arg("fix").arg(file.path()))?;
         let buf = String::from_utf8(out.stdout)?;
 
         let fix: ValeFix = serde_json::from_str(&buf)?;
         Ok(fix)
     }
 
-    pub(crate) fn upload_rule(
+    /// `test_rule` lints `text`, as a file with the extension `ext`, using only
+    /// the rule `check` (e.g., `Style.Rule`) with the definition `rule`.
+    ///
+    /// Vale runs in a temporary directory with a minimal config, so the
+    /// rule can be tested before it's saved. Rules that rely on other files
+    /// in the `StylesPath`, such as vocabularies, won't find them.
+    pub(crate) fn test_rule(
+        &self,
+        check: &str,
+        rule: &str,
+        text: &str,
+        ext: &str,
+    ) -> Result<Vec<ValeAlert>, Error> {
+        let (style, name) = check.split_once('.').ok_or(Error::from(format!(
+            "'{}' isn't a valid check name.",
+            check
+        )))?;
+
+        let dir = tempfile::tempdir()?;
+        let styles = dir.path().join("styles").join(style);
+        std::fs::create_dir_all(&styles)?;
+        std::fs::write(styles.join(format!("{}.yml", name)), rule)?;
+
+        let config = dir.path().join(".vale.ini");
+        std::fs::write(
+            &config,
+            format!(
+                "StylesPath = styles\nMinAlertLevel = suggestion\n\n[*]\n{} = YES\n",
+                check
+            ),
+        )?;
+
+        let sample = dir.path().join(format!("sample.{}", ext));
+        std::fs::write(&sample, text)?;
+
+        let mut results = self.run(sample, config.display().to_string(), String::new())?;
+        Ok(results.drain().flat_map(|(_, alerts)| alerts).collect())
+    }
+
+    pub(crate) async fn upload_rule(
         &self,
         config_path: String,
         cwd: String,
         rule: String,
     ) -> Result<regex101::Regex101Session, Error> {
         let rule = self.compile(config_path, cwd.clone(), rule)?;
-        let session = regex101::upload(rule.pattern)?;
+        let session = regex101::upload(rule.pattern).await?;
         Ok(session)
     }
 
-    fn compile(
+    pub(crate) fn compile(
         &self,
         config_path: String,
         cwd: String,
@@ -272,10 +678,11 @@ impl ValeManager {
         args.push(rule);
 
         let exe = self.exe_path(false)?;
-        let compiled = Command::new(exe.as_os_str())
-            .current_dir(cwd.clone())
-            .args(args)
-            .output()?;
+        let compiled = output(
+            Command::new(exe.as_os_str())
+                .current_dir(cwd.clone())
+                .args(args),
+        )?;
 
         let buf = String::from_utf8(compiled.stdout)?;
         let rule: CompiledRule = serde_json::from_str(&buf)?;
@@ -284,16 +691,26 @@ impl ValeManager {
     }
 
     fn exe_path(&self, managed: bool) -> Result<PathBuf, Error> {
-        if self.managed_exe.exists() {
-            return Ok(self.managed_exe.clone());
+        if let Some(custom) = self.custom_exe.read().unwrap().as_ref() {
+            if !managed && custom.exists() {
+                return Ok(custom.clone());
+            }
+        }
+
+        let installed = self.managed_bin().join(&self.exe_name);
+        let legacy = self.legacy_bin.join(&self.exe_name);
+        if installed.exists() {
+            return Ok(installed);
+        } else if legacy.exists() {
+            return Ok(legacy);
         } else if self.fallback_exe.exists() && !managed {
             return Ok(self.fallback_exe.clone());
         }
         Err(Error::from("Vale is not installed."))
     }
 
-    fn newer_version(&self) -> Result<Option<String>, Error> {
-        let latest = self.fetch_version()?;
+    async fn newer_version(&self) -> Result<Option<String>, Error> {
+        let latest = self.fetch_version().await?;
         match self.version(true) {
             Ok(current) => {
                 let v1 = Version::parse(&current)?;
@@ -322,14 +739,17 @@ impl ValeManager {
         Err(Error::Msg(stderr))
     }
 
-    /// `fetch_version` returns the latest version of Vale.
-    fn fetch_version(&self) -> Result<String, Error> {
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("vale-ls")
-            .build()?;
+    /// `fetch_version` returns the latest version of Vale, including release
+    /// candidates on the prerelease channel.
+    async fn fetch_version(&self) -> Result<String, Error> {
+        if self.prerelease.load(Ordering::Relaxed) {
+            let resp = http::client()?.get(ALL_RELEASES).send().await?;
+            let releases: Vec<Release> = resp.json().await?;
+            return latest_release(releases).ok_or(Error::from("No releases found."));
+        }
 
-        let resp = client.get(LATEST).send()?;
-        let info: Release = resp.json()?;
+        let resp = http::client()?.get(LATEST).send().await?;
+        let info: Release = resp.json().await?;
 
         let tag = info.tag_name.strip_prefix("v").unwrap().to_string();
         Ok(tag)
@@ -343,42 +763,169 @@ impl ValeManager {
     /// * `path` - A path to the directory where Vale should be installed.
     /// * `version` - A string representing the version to be installed.
     /// * `arch` - A string representing the architecture to be installed.
-    fn install(&self, path: &Path, v: &str, arch: &str) -> Result<(), Error> {
+    async fn install(&self, path: &Path, v: &str, arch: &str) -> Result<(), Error> {
         let mut asset = format!("/v{}/vale_{}_{}.tar.gz", v, v, arch);
         if arch.to_lowercase().contains("windows") {
             asset = format!("/v{}/vale_{}_{}.zip", v, v, arch);
         }
         let url = format!("{}{}", RELEASES, asset);
 
-        let resp = reqwest::blocking::get(url)?.bytes()?;
-        let archive = resp.to_vec();
+        // Stream the archive to disk rather than holding it all in memory.
+        let mut archive = tempfile::tempfile()?;
+        http::download(&url, &mut archive).await?;
+        archive.seek(io::SeekFrom::Start(0))?;
+
+        // Extracting is blocking work, so keep it off the async workers.
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || -> Result<(), Error> {
+            if asset.ends_with(".zip") {
+                zip_extract::extract(archive, &path, true)?;
+            } else {
+                Archive::new(GzDecoder::new(archive)).unpack(&path)?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| Error::from(e.to_string()))?
+    }
+}
+
+/// `output` runs `cmd` to completion, logging how it went.
+fn output(cmd: &mut Command) -> io::Result<Output> {
+    let start = Instant::now();
+    let result = cmd.output();
+    log_run(cmd, start, &result);
+    result
+}
 
-        let buf = io::Cursor::new(archive);
-        if asset.ends_with(".zip") {
-            zip_extract::extract(buf, path, true)?;
-        } else {
-            Archive::new(GzDecoder::new(buf)).unpack(path)?;
+/// `log_run` records a run of Vale: its arguments, working directory, how long
+/// it took, and how it exited (along with its stderr, as a warning, if it
+/// failed).
+fn log_run(cmd: &Command, start: Instant, result: &io::Result<Output>) {
+    let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+    let cwd = cmd.get_current_dir().map(|d| d.display().to_string());
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(out) => {
+            tracing::info!(
+                program = %cmd.get_program().to_string_lossy(),
+                args = ?args,
+                cwd = ?cwd,
+                elapsed_ms,
+                status = ?out.status.code(),
+                "Ran Vale"
+            );
+            if !out.status.success() && !out.stderr.is_empty() {
+                tracing::warn!(stderr = %String::from_utf8_lossy(&out.stderr), "Vale failed");
+            }
         }
+        Err(err) => tracing::warn!(
+            program = %cmd.get_program().to_string_lossy(),
+            args = ?args,
+            cwd = ?cwd,
+            elapsed_ms,
+            error = %err,
+            "Failed to run Vale"
+        ),
+    }
+}
 
-        Ok(())
+/// `default_bin_dir` returns where we install Vale unless told otherwise: a
+/// `vale-ls` directory in the platform's data directory (e.g.,
+/// `~/.local/share/vale-ls` on Linux), or `legacy` if there isn't one.
+fn default_bin_dir(legacy: &Path) -> PathBuf {
+    match dirs::data_dir() {
+        Some(dir) => dir.join("vale-ls"),
+        None => legacy.to_path_buf(),
     }
 }
 
+/// `latest_release` returns the highest version among `releases`, ignoring
+/// drafts.
+fn latest_release(releases: Vec<Release>) -> Option<String> {
+    releases
+        .into_iter()
+        .filter(|r| !r.draft)
+        .filter_map(|r| Version::parse(r.tag_name.trim_start_matches('v')).ok())
+        .max()
+        .map(|v| v.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn version() {
+    fn metrics() {
+        let m = ValeMetrics {
+            words: 100.0,
+            sentences: 5.0,
+            syllables: 150.0,
+            ..ValeMetrics::default()
+        };
+        assert_eq!(m.words_per_sentence(), 20.0);
+        assert!((m.flesch_reading_ease() - 59.635).abs() < 0.001);
+        assert_eq!(ValeMetrics::default().flesch_reading_ease(), 0.0);
+
+        let m = ValeMetrics {
+            characters: 500.0,
+            complex_words: 10.0,
+            polysyllabic_words: 10.0,
+            ..m
+        };
+        assert!((m.flesch_kincaid() - 9.91).abs() < 0.001);
+        assert!((m.gunning_fog() - 12.0).abs() < 0.001);
+        assert!((m.coleman_liau() - 12.12).abs() < 0.001);
+        assert!((m.automated_readability() - 12.12).abs() < 0.001);
+        assert!(m.smog() > 0.0);
+        assert_eq!(ValeMetrics::default().smog(), 0.0);
+    }
+
+    #[test]
+    fn releases() {
+        let releases: Vec<Release> = serde_json::from_str(
+            r#"[
+                {"tag_name": "v3.1.0"},
+                {"tag_name": "v3.2.0-rc.1"},
+                {"tag_name": "v3.3.0", "draft": true},
+                {"tag_name": "nightly"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(latest_release(releases), Some("3.2.0-rc.1".to_string()));
+        assert_eq!(latest_release(vec![]), None);
+    }
+
+    #[test]
+    fn asset_arch() {
+        let mut cli = ValeManager::new();
+        cli.arch = Some("Linux_64-bit".to_string());
+        cli.musl = true;
+        assert!(cli.asset_arch().is_err());
+
+        cli.set_arch("Linux_arm64");
+        assert_eq!(cli.asset_arch().unwrap(), "Linux_arm64");
+
+        cli.set_arch("");
+        cli.musl = false;
+        assert_eq!(cli.asset_arch().unwrap(), "Linux_64-bit");
+
+        cli.arch = None;
+        assert!(cli.asset_arch().is_err());
+    }
+
+    #[tokio::test]
+    async fn version() {
         let mgr = ValeManager::new();
 
-        let out = mgr.newer_version().unwrap();
+        let out = mgr.newer_version().await.unwrap();
         assert!(out.is_some());
 
         let v1 = Version::parse(&out.unwrap()).unwrap();
         assert!(v1 >= Version::parse("2.0.0").unwrap());
 
-        let v2 = Version::parse(&mgr.fetch_version().unwrap()).unwrap();
+        let v2 = Version::parse(&mgr.fetch_version().await.unwrap()).unwrap();
         assert!(v2 >= Version::parse("2.0.0").unwrap());
     }
 }
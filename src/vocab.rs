@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use tower_lsp::lsp_types::*;
+
+/// The files that make up a vocabulary.
+pub(crate) const LISTS: [&str; 2] = ["accept.txt", "reject.txt"];
+
+/// `is_vocab` reports whether `path` is a vocabulary's `accept.txt` or
+/// `reject.txt`, either in `Vocab/<name>` or (as of Vale 3)
+/// `config/vocabularies/<name>`.
+pub(crate) fn is_vocab(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let parent = path
+        .parent()
+        .and_then(|p| p.parent())
+        .and_then(|p| p.file_name())
+        .and_then(|n| n.to_str());
+    LISTS.contains(&name) && matches!(parent, Some("Vocab") | Some("vocabularies"))
+}
+
+/// `sibling` returns the other list of the vocabulary `path` belongs to: the
+/// `reject.txt` for an `accept.txt`, and vice versa.
+pub(crate) fn sibling(path: &Path) -> Option<(&'static str, std::path::PathBuf)> {
+    let name = path.file_name()?.to_str()?;
+    let other = LISTS.into_iter().find(|l| *l != name)?;
+    Some((other, path.with_file_name(other)))
+}
+
+/// `terms` returns the non-blank lines of a list along with their line
+/// numbers.
+fn terms(src: &str) -> impl Iterator<Item = (usize, &str)> {
+    src.lines()
+        .enumerate()
+        .map(|(i, l)| (i, l.trim()))
+        .filter(|(_, l)| !l.is_empty())
+}
+
+/// `normalize` sorts a list's terms (ignoring case), removes duplicates, and
+/// drops blank lines.
+pub(crate) fn normalize(src: &str) -> String {
+    let mut terms: Vec<&str> = terms(src).map(|(_, t)| t).collect();
+    terms.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b)));
+    terms.dedup();
+
+    let mut out = terms.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// `diagnose` checks a vocabulary list for invalid patterns, duplicate terms,
+/// and terms that (ignoring case) also appear in `other`, the vocabulary's
+/// other list.
+pub(crate) fn diagnose(src: &str, other: Option<(&str, &str)>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let conflicts: HashMap<String, usize> = match other {
+        Some((_, other)) => terms(other).map(|(i, t)| (t.to_lowercase(), i)).collect(),
+        None => HashMap::new(),
+    };
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (i, term) in terms(src) {
+        let line = src.lines().nth(i).unwrap_or("");
+        let start = line.chars().count() - line.trim_start().chars().count();
+        let range = Range::new(
+            Position::new(i as u32, start as u32),
+            Position::new(i as u32, (start + term.chars().count()) as u32),
+        );
+
+        if let Err(e) = fancy_regex::Regex::new(term) {
+            diagnostics.push(vocab_diagnostic(
+                range,
+                DiagnosticSeverity::ERROR,
+                format!("Invalid regex: {}", e),
+            ));
+        }
+
+        if let Some(first) = seen.get(term) {
+            diagnostics.push(vocab_diagnostic(
+                range,
+                DiagnosticSeverity::WARNING,
+                format!("Duplicate term (also on line {}).", first + 1),
+            ));
+        } else {
+            seen.insert(term, i);
+        }
+
+        if let (Some(j), Some((name, _))) = (conflicts.get(&term.to_lowercase()), other) {
+            diagnostics.push(vocab_diagnostic(
+                range,
+                DiagnosticSeverity::WARNING,
+                format!("Conflicts with line {} of {}.", j + 1, name),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+fn vocab_diagnostic(range: Range, severity: DiagnosticSeverity, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        source: Some("vale-ls".to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vocab_files() {
+        assert!(is_vocab(Path::new("styles/Vocab/Base/accept.txt")));
+        assert!(is_vocab(Path::new(
+            "styles/config/vocabularies/Base/reject.txt"
+        )));
+        assert!(!is_vocab(Path::new("styles/Base/accept.txt")));
+        assert!(!is_vocab(Path::new("styles/Vocab/Base/notes.txt")));
+
+        let (name, path) = sibling(Path::new("Vocab/Base/accept.txt")).unwrap();
+        assert_eq!(name, "reject.txt");
+        assert_eq!(path, Path::new("Vocab/Base/reject.txt"));
+    }
+
+    #[test]
+    fn sorting() {
+        let src = "beta\n\nAlpha\nalpha\nbeta\n  Gamma  \n";
+        assert_eq!(normalize(src), "Alpha\nalpha\nbeta\nGamma\n");
+        assert_eq!(normalize("\n\n"), "");
+    }
+
+    #[test]
+    fn diagnostics() {
+        let src = "Vale\n\n[Oo]bservability\nVale\nfoo(\nutilize\n";
+        let found = diagnose(src, Some(("reject.txt", "Utilize\n")));
+        let messages: Vec<(u32, &str)> = found
+            .iter()
+            .map(|d| (d.range.start.line, d.message.as_str()))
+            .collect();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], (3, "Duplicate term (also on line 1)."));
+        assert_eq!(messages[1].0, 4);
+        assert!(messages[1].1.starts_with("Invalid regex"));
+        assert_eq!(messages[2], (5, "Conflicts with line 1 of reject.txt."));
+    }
+}
@@ -1,7 +1,8 @@
 use std::borrow::Cow;
 
+use regex::Regex;
 use tower_lsp::lsp_types::*;
-use yaml_rust::YamlLoader;
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
 
 use crate::error::Error;
 
@@ -23,6 +24,9 @@ pub enum Extends {
 pub struct Rule {
     pub extends: Extends,
     pub source: String,
+    pub message: String,
+    pub description: String,
+    pub level: String,
 }
 
 fn vec_to_completions(vec: Vec<&str>) -> Vec<CompletionItem> {
@@ -35,16 +39,495 @@ fn vec_to_completions(vec: Vec<&str>) -> Vec<CompletionItem> {
         .collect()
 }
 
+/// `scope_info` returns the documentation for one of Vale's scopes.
+fn scope_info(scope: &str) -> Option<&'static str> {
+    match scope {
+        "heading" => Some(include_str!("../doc/yml/scope/heading.md")),
+        "heading.h1" | "heading.h2" | "heading.h3" | "heading.h4" | "heading.h5" | "heading.h6" => {
+            Some(include_str!("../doc/yml/scope/heading.level.md"))
+        }
+        "paragraph" => Some(include_str!("../doc/yml/scope/paragraph.md")),
+        "sentence" => Some(include_str!("../doc/yml/scope/sentence.md")),
+        "list" => Some(include_str!("../doc/yml/scope/list.md")),
+        "table.header" => Some(include_str!("../doc/yml/scope/table.header.md")),
+        "table.cell" => Some(include_str!("../doc/yml/scope/table.cell.md")),
+        "table.caption" => Some(include_str!("../doc/yml/scope/table.caption.md")),
+        "figure.caption" => Some(include_str!("../doc/yml/scope/figure.caption.md")),
+        "blockquote" => Some(include_str!("../doc/yml/scope/blockquote.md")),
+        "alt" => Some(include_str!("../doc/yml/scope/alt.md")),
+        "summary" => Some(include_str!("../doc/yml/scope/summary.md")),
+        "text" => Some(include_str!("../doc/yml/scope/text.md")),
+        "raw" => Some(include_str!("../doc/yml/scope/raw.md")),
+        "code" => Some(include_str!("../doc/yml/scope/code.md")),
+        "link" => Some(include_str!("../doc/yml/scope/link.md")),
+        "comment" => Some(include_str!("../doc/yml/scope/comment.md")),
+        "comment.line" => Some(include_str!("../doc/yml/scope/comment.line.md")),
+        "comment.block" => Some(include_str!("../doc/yml/scope/comment.block.md")),
+        _ => None,
+    }
+}
+
+/// `complete_scopes` returns a completion for each of Vale's scopes.
+fn complete_scopes() -> Vec<CompletionItem> {
+    let scopes = vec![
+        "heading",
+        "heading.h1",
+        "heading.h2",
+        "heading.h3",
+        "heading.h4",
+        "heading.h5",
+        "heading.h6",
+        "paragraph",
+        "sentence",
+        "list",
+        "table.header",
+        "table.cell",
+        "table.caption",
+        "figure.caption",
+        "blockquote",
+        "alt",
+        "summary",
+        "text",
+        "raw",
+        "code",
+        "link",
+        "comment",
+        "comment.line",
+        "comment.block",
+    ];
+
+    scopes
+        .into_iter()
+        .map(|scope| CompletionItem {
+            label: scope.to_string(),
+            kind: Some(CompletionItemKind::VALUE),
+            documentation: scope_info(scope).map(|info| {
+                Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: info.to_string(),
+                })
+            }),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+/// `find_key` returns the range of a top-level `key:` in a rule's source.
+pub(crate) fn find_key(src: &str, key: &str) -> Option<Range> {
+    let prefix = format!("{}:", key);
+    src.lines()
+        .position(|line| line.starts_with(&prefix))
+        .map(|i| {
+            Range::new(
+                Position::new(i as u32, 0),
+                Position::new(i as u32, key.len() as u32),
+            )
+        })
+}
+
+/// Rule skeletons offered in empty rule files, written as LSP snippets.
+///
+/// NOTE: A `}` inside a placeholder (e.g., in a `{m,n}` quantifier) must be
+/// escaped as `\}`, or it ends the placeholder.
+const TEMPLATES: [(&str, &str); 11] = [
+    (
+        "existence",
+        "extends: existence\nmessage: \"${1:Consider removing '%s'.}\"\nlevel: ${2|suggestion,warning,error|}\nignorecase: ${3|true,false|}\ntokens:\n  - ${4:token}\n",
+    ),
+    (
+        "substitution",
+        "extends: substitution\nmessage: \"${1:Use '%s' instead of '%s'.}\"\nlevel: ${2|suggestion,warning,error|}\nignorecase: ${3|true,false|}\nswap:\n  ${4:bad}: ${5:good}\n",
+    ),
+    (
+        "occurrence",
+        "extends: occurrence\nmessage: \"${1:More than %s occurrences.}\"\nlevel: ${2|suggestion,warning,error|}\nscope: ${3:sentence}\nmax: ${4:3}\ntoken: '${5:,}'\n",
+    ),
+    (
+        "repetition",
+        "extends: repetition\nmessage: \"${1:'%s' is repeated!}\"\nlevel: ${2|suggestion,warning,error|}\nalpha: true\ntokens:\n  - '${3:[^\\s]+}'\n",
+    ),
+    (
+        "consistency",
+        "extends: consistency\nmessage: \"${1:Inconsistent spelling of '%s'.}\"\nlevel: ${2|suggestion,warning,error|}\nignorecase: ${3|true,false|}\neither:\n  ${4:advisor}: ${5:adviser}\n",
+    ),
+    (
+        "conditional",
+        "extends: conditional\nmessage: \"${1:'%s' has no definition.}\"\nlevel: ${2|suggestion,warning,error|}\nfirst: '${3:\\b([A-Z]{3,5\\})\\b}'\nsecond: '${4:(?:\\b[A-Z][a-z]+ )+\\(([A-Z]{3,5\\})\\)}'\n",
+    ),
+    (
+        "capitalization",
+        "extends: capitalization\nmessage: \"${1:'%s' should be in title case.}\"\nlevel: ${2|suggestion,warning,error|}\nscope: ${3:heading}\nmatch: ${4|$title,$sentence,$lower,$upper|}\n",
+    ),
+    (
+        "metric",
+        "extends: metric\nmessage: \"${1:Try to keep the score (%s) below 8.}\"\nlevel: ${2|suggestion,warning,error|}\nformula: |\n  ${3:(0.39 * (words / sentences)) + (11.8 * (syllables / words)) - 15.59}\ncondition: \"${4:> 8}\"\n",
+    ),
+    (
+        "spelling",
+        "extends: spelling\nmessage: \"${1:Did you really mean '%s'?}\"\nlevel: ${2|suggestion,warning,error|}\nignore:\n  - ${3:ignore.txt}\n",
+    ),
+    (
+        "sequence",
+        "extends: sequence\nmessage: \"${1:Message}\"\nlevel: ${2|suggestion,warning,error|}\ntokens:\n  - tag: ${3:MD}\n  - pattern: ${4:be}\n",
+    ),
+    (
+        "script",
+        "extends: script\nmessage: \"${1:Message}\"\nlevel: ${2|suggestion,warning,error|}\nscope: raw\nscript: |\n  text := import(\"text\")\n  matches := []\n  ${3}\n",
+    ),
+];
+
+/// `templates` returns a completion for each rule skeleton.
+///
+/// If the client doesn't support snippets, the tab stops are replaced with
+/// their (unescaped) placeholders.
+pub(crate) fn templates(snippets: bool) -> Vec<CompletionItem> {
+    let choice = Regex::new(r"\$\{\d+\|([^,|]*)[^}]*\}").unwrap();
+    let placeholder = Regex::new(r"\$\{\d+:?((?:\\.|[^}\\])*)\}").unwrap();
+
+    TEMPLATES
+        .iter()
+        .map(|(name, body)| {
+            let (text, format) = if snippets {
+                (body.to_string(), InsertTextFormat::SNIPPET)
+            } else {
+                let plain = choice.replace_all(body, "$1");
+                let plain = placeholder.replace_all(&plain, |caps: &regex::Captures| {
+                    caps[1].replace("\\}", "}").replace("\\$", "$")
+                });
+                (plain.to_string(), InsertTextFormat::PLAIN_TEXT)
+            };
+            CompletionItem {
+                label: format!("{} rule", name),
+                kind: Some(CompletionItemKind::SNIPPET),
+                detail: Some(format!("A new `{}` rule", name)),
+                insert_text: Some(text),
+                insert_text_format: Some(format),
+                ..CompletionItem::default()
+            }
+        })
+        .collect()
+}
+
+/// `example` returns the bundled example rule for an `extends` type.
+pub(crate) fn example(extends: &str) -> Option<&'static str> {
+    let doc = match extends {
+        "existence" => include_str!("../doc/yml/existence/example.md"),
+        "substitution" => include_str!("../doc/yml/substitution/example.md"),
+        "occurrence" => include_str!("../doc/yml/occurrence/example.md"),
+        "repetition" => include_str!("../doc/yml/repetition/example.md"),
+        "consistency" => include_str!("../doc/yml/consistency/example.md"),
+        "conditional" => include_str!("../doc/yml/conditional/example.md"),
+        "capitalization" => include_str!("../doc/yml/capitalization/example.md"),
+        "metric" => include_str!("../doc/yml/metric/example.md"),
+        "spelling" => include_str!("../doc/yml/spelling/example.md"),
+        "sequence" => include_str!("../doc/yml/sequence/example.md"),
+        "script" => include_str!("../doc/yml/script/example.md"),
+        _ => return None,
+    };
+    doc.trim_end()
+        .strip_prefix("```yaml\n")?
+        .strip_suffix("```")
+}
+
+/// `unquote` strips YAML quotes from a scalar.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        value[1..value.len() - 1].replace("''", "'")
+    } else if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        value[1..value.len() - 1]
+            .replace("\\\\", "\\")
+            .replace("\\\"", "\"")
+    } else {
+        value.to_string()
+    }
+}
+
+/// `check_pattern` reports a diagnostic if `pattern` isn't a valid regular
+/// expression.
+///
+/// Vale supports lookarounds and backreferences, so we validate against
+/// `fancy_regex` rather than `regex`.
+fn check_pattern(pattern: &str, line: usize, start: usize, len: usize) -> Option<Diagnostic> {
+    match fancy_regex::Regex::new(pattern) {
+        Ok(_) => None,
+        Err(e) => Some(Diagnostic {
+            range: Range::new(
+                Position::new(line as u32, start as u32),
+                Position::new(line as u32, (start + len) as u32),
+            ),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("vale-ls".to_string()),
+            message: format!("Invalid regex: {}", e),
+            ..Diagnostic::default()
+        }),
+    }
+}
+
+/// `diagnose` validates the regular expressions in a rule's `tokens`, `swap`,
+/// `exceptions`, `first`, and `second` keys.
+pub(crate) fn diagnose(src: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut key = "";
+    for (i, line) in src.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let indent = line.chars().count() - line.trim_start().chars().count();
+        let top_level = indent == 0 && !trimmed.starts_with('-');
+        if top_level {
+            let (k, value) = trimmed.split_once(':').unwrap_or((trimmed, ""));
+            key = k;
+            if (key == "first" || key == "second") && !value.trim().is_empty() {
+                let start = line.chars().count() - line[k.len() + 1..].trim_start().chars().count();
+                let value = value.trim();
+                diagnostics.extend(check_pattern(
+                    &unquote(value),
+                    i,
+                    start,
+                    value.chars().count(),
+                ));
+            }
+            continue;
+        }
+
+        match key {
+            "tokens" | "exceptions" => {
+                if let Some(item) = trimmed.strip_prefix('-') {
+                    let item = item.trim();
+                    if item.is_empty() {
+                        continue;
+                    }
+                    let start = line.chars().count() - item.chars().count();
+                    diagnostics.extend(check_pattern(
+                        &unquote(item),
+                        i,
+                        start,
+                        item.chars().count(),
+                    ));
+                }
+            }
+            "swap" => {
+                let pattern = match trimmed.chars().next() {
+                    Some(q @ ('\'' | '"')) => match trimmed[1..].find(q) {
+                        Some(end) => &trimmed[..end + 2],
+                        None => continue,
+                    },
+                    _ => match trimmed.find(": ") {
+                        Some(end) => &trimmed[..end],
+                        None => trimmed.trim_end_matches(':'),
+                    },
+                };
+                diagnostics.extend(check_pattern(
+                    &unquote(pattern),
+                    i,
+                    indent,
+                    pattern.chars().count(),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// `block` returns the top-level key that `line` is nested under, along with
+/// the text of that key's block.
+pub(crate) fn block(src: &str, line: usize) -> Option<(String, String)> {
+    let lines: Vec<&str> = src.lines().collect();
+
+    let current = lines.get(line).copied().unwrap_or("");
+    if !current.starts_with(char::is_whitespace) && !current.trim().is_empty() {
+        return None;
+    }
+
+    let start = (0..line.min(lines.len()))
+        .rev()
+        .find(|&i| !lines[i].starts_with(char::is_whitespace) && !lines[i].trim().is_empty())?;
+    let key = lines[start]
+        .split(':')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    let end = lines
+        .iter()
+        .skip(start + 1)
+        .position(|l| !l.starts_with(char::is_whitespace) && !l.trim().is_empty())
+        .map_or(lines.len(), |n| start + 1 + n);
+
+    Some((key, lines[start..end].join("\n")))
+}
+
+/// `selected_token` returns the pattern in a selection from a `tokens` list or,
+/// if `swap` is set, a `swap` map: list markers, quotes, and swap values are
+/// dropped, so selecting a whole line works as well as selecting the pattern.
+pub(crate) fn selected_token(selection: &str, swap: bool) -> String {
+    let selection = selection.trim();
+    let selection = selection.strip_prefix("- ").unwrap_or(selection);
+    if !swap {
+        return unquote(selection);
+    }
+
+    let key = match selection.chars().next() {
+        Some(q @ ('\'' | '"')) => match selection[1..].find(q) {
+            Some(end) => &selection[..end + 2],
+            None => selection,
+        },
+        _ => selection.split(": ").next().unwrap_or(selection),
+    };
+    unquote(key.trim_end_matches(':'))
+}
+
+/// `with_token` returns the rule in `src` with its `tokens` (or `swap`)
+/// replaced by `token` alone, so that Vale compiles just that pattern.
+pub(crate) fn with_token(src: &str, token: &str) -> Option<String> {
+    let mut docs = YamlLoader::load_from_str(src).ok()?;
+    let rule = match docs.first_mut()? {
+        Yaml::Hash(rule) => rule,
+        _ => return None,
+    };
+
+    let pattern = Yaml::String(token.to_string());
+    let tokens = Yaml::String("tokens".to_string());
+    let swap = Yaml::String("swap".to_string());
+    if rule.contains_key(&tokens) {
+        rule.insert(tokens, Yaml::Array(vec![pattern]));
+    } else if let Some(Yaml::Hash(map)) = rule.get(&swap) {
+        let value = map.get(&pattern).cloned();
+        let mut single = yaml_rust::yaml::Hash::new();
+        single.insert(pattern, value.unwrap_or(Yaml::String(String::new())));
+        rule.insert(swap, Yaml::Hash(single));
+    } else {
+        return None;
+    }
+
+    let mut out = String::new();
+    YamlEmitter::new(&mut out).dump(&docs[0]).ok()?;
+    Some(out)
+}
+
+/// `complete_action` completes the keys and values of an `action` block.
+fn complete_action(line: &str, block: &str) -> Vec<CompletionItem> {
+    let trimmed = line.trim_start();
+
+    if trimmed.starts_with("name:") {
+        return ["suggest", "replace", "remove", "edit", "convert"]
+            .into_iter()
+            .map(|name| CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                documentation: action_info(name).map(|info| {
+                    Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: info.to_string(),
+                    })
+                }),
+                ..CompletionItem::default()
+            })
+            .collect();
+    } else if trimmed.starts_with('-') {
+        let name = Regex::new(r"(?m)^\s+name:\s*(\w+)").unwrap();
+        let params = match name.captures(block).map(|c| c[1].to_string()).as_deref() {
+            Some("suggest") => vec!["spellings"],
+            Some("edit") => vec![
+                "regex",
+                "trim_right",
+                "trim_left",
+                "trim",
+                "truncate",
+                "split",
+            ],
+            Some("convert") => vec!["simple"],
+            _ => vec![],
+        };
+        return vec_to_completions(params);
+    } else if !trimmed.contains(':') {
+        return ["name", "params"]
+            .into_iter()
+            .map(|key| CompletionItem {
+                label: key.to_string(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                insert_text: Some(format!("{}: ", key)),
+                documentation: action_info(key).map(|info| {
+                    Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: info.to_string(),
+                    })
+                }),
+                ..CompletionItem::default()
+            })
+            .collect();
+    }
+
+    vec![]
+}
+
+/// `action_info` returns the documentation for an `action` key or name.
+fn action_info(token: &str) -> Option<&'static str> {
+    match token {
+        "name" => Some(include_str!("../doc/yml/action/name.md")),
+        "params" => Some(include_str!("../doc/yml/action/params.md")),
+        "suggest" => Some(include_str!("../doc/yml/action/suggest.md")),
+        "replace" => Some(include_str!("../doc/yml/action/replace.md")),
+        "remove" => Some(include_str!("../doc/yml/action/remove.md")),
+        "edit" => Some(include_str!("../doc/yml/action/edit.md")),
+        "convert" => Some(include_str!("../doc/yml/action/convert.md")),
+        _ => None,
+    }
+}
+
+/// `folding_ranges` folds the (potentially long) `tokens`, `swap`, and
+/// `exceptions` lists of a rule.
+pub(crate) fn folding_ranges(src: &str) -> Vec<FoldingRange> {
+    let lines: Vec<&str> = src.lines().collect();
+
+    let mut ranges = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let foldable = ["tokens:", "swap:", "exceptions:"]
+            .iter()
+            .any(|k| line.starts_with(k));
+        if !foldable {
+            continue;
+        }
+
+        let mut end = i;
+        for (j, next) in lines.iter().enumerate().skip(i + 1) {
+            let top_level = !next.starts_with(char::is_whitespace) && !next.starts_with('-');
+            if top_level && !next.trim().is_empty() {
+                break;
+            } else if !next.trim().is_empty() {
+                end = j;
+            }
+        }
+
+        if end > i {
+            ranges.push(FoldingRange {
+                start_line: i as u32,
+                start_character: None,
+                end_line: end as u32,
+                end_character: None,
+                kind: Some(FoldingRangeKind::Region),
+                collapsed_text: None,
+            });
+        }
+    }
+
+    ranges
+}
+
 impl Rule {
     pub(crate) fn new(rule_path: &str) -> Result<Rule, Error> {
         let src = std::fs::read_to_string(rule_path)?;
         match YamlLoader::load_from_str(&src) {
             Ok(docs) => {
                 if docs.len() < 1 {
-                    return Ok(Rule {
-                        extends: Extends::Invalid,
-                        source: "".to_string(),
-                    });
+                    return Ok(Rule::invalid());
                 }
                 let doc = docs[0].clone();
                 let extends = match doc["extends"].as_str().unwrap_or("invalid") {
@@ -64,23 +547,58 @@ impl Rule {
                 Ok(Rule {
                     extends,
                     source: doc["link"].as_str().unwrap_or("").to_string(),
+                    message: doc["message"].as_str().unwrap_or("").to_string(),
+                    description: doc["description"].as_str().unwrap_or("").to_string(),
+                    level: doc["level"].as_str().unwrap_or("").to_string(),
                 })
             }
-            Err(_) => Ok(Rule {
-                extends: Extends::Invalid,
-                source: "".to_string(),
-            }),
+            Err(_) => Ok(Rule::invalid()),
+        }
+    }
+
+    fn invalid() -> Rule {
+        Rule {
+            extends: Extends::Invalid,
+            source: "".to_string(),
+            message: "".to_string(),
+            description: "".to_string(),
+            level: "".to_string(),
+        }
+    }
+
+    /// Returns a Markdown summary of the rule, as shown when hovering an alert.
+    pub(crate) fn summary(&self, check: &str) -> String {
+        let mut info = format!("**{}**", check);
+        if !self.level.is_empty() {
+            info += &format!(" (`{}`)", self.level);
+        }
+        if !self.message.is_empty() {
+            info += &format!("\n\n{}", self.message);
         }
+        if !self.description.is_empty() {
+            info += &format!("\n\n{}", self.description);
+        }
+        if !self.source.is_empty() {
+            info += &format!("\n\n[Documentation]({})", self.source);
+        }
+        info
     }
 
     pub(crate) fn source(&self) -> String {
         self.source.clone()
     }
 
-    pub(crate) fn complete(&self, line: &str) -> Result<Vec<CompletionItem>, Error> {
+    pub(crate) fn complete(
+        &self,
+        line: &str,
+        block: Option<(String, String)>,
+    ) -> Result<Vec<CompletionItem>, Error> {
         let mut completions = Vec::new();
+        let line = line.trim_end_matches(['\r', '\n']);
 
-        if line.contains("extends:") {
+        if let Some((_, text)) = block.filter(|(key, _)| key == "action") {
+            completions = complete_action(line, &text);
+        } else if line.contains("extends:") {
             completions = vec_to_completions(vec![
                 "existence",
                 "substitution",
@@ -96,11 +614,69 @@ impl Rule {
             ]);
         } else if line.contains("level:") {
             completions = vec_to_completions(vec!["suggestion", "warning", "error"]);
+        } else if line.starts_with("scope:") {
+            completions = complete_scopes();
+        } else if !line.contains(':') && !line.starts_with(char::is_whitespace) {
+            completions = self.complete_keys();
         }
 
         Ok(completions)
     }
 
+    /// Returns the keys that are valid for the rule's `extends` type.
+    pub(crate) fn keys(&self) -> Vec<&'static str> {
+        let mut keys = vec![
+            "extends", "message", "level", "scope", "link", "limit", "action",
+        ];
+        keys.extend(match self.extends {
+            Extends::Existence => vec![
+                "append",
+                "ignorecase",
+                "nonword",
+                "raw",
+                "tokens",
+                "exceptions",
+            ],
+            Extends::Substitution => vec!["append", "ignorecase", "nonword", "exceptions", "swap"],
+            Extends::Occurrence => vec!["min", "max", "token"],
+            Extends::Repetition => vec!["alpha", "tokens"],
+            Extends::Consistency => vec!["either", "nonword", "ignorecase"],
+            Extends::Conditional => vec!["first", "second", "ignorecase"],
+            Extends::Capitalization => vec!["exceptions", "match", "style"],
+            Extends::Metric => vec!["formula", "condition"],
+            Extends::Spelling => vec![
+                "append",
+                "custom",
+                "dicpath",
+                "dictionaries",
+                "filters",
+                "ignore",
+            ],
+            Extends::Sequence => vec!["ignorecase", "tokens"],
+            Extends::Script => vec!["script"],
+            Extends::Invalid => vec![],
+        });
+        keys
+    }
+
+    fn complete_keys(&self) -> Vec<CompletionItem> {
+        self.keys()
+            .into_iter()
+            .map(|key| CompletionItem {
+                label: key.to_string(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                insert_text: Some(format!("{}: ", key)),
+                documentation: self.token_info(key).map(|info| {
+                    Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: info.to_string(),
+                    })
+                }),
+                ..CompletionItem::default()
+            })
+            .collect()
+    }
+
     pub(crate) fn can_compile(&self) -> bool {
         match self.extends {
             Extends::Existence => true,
@@ -150,6 +726,9 @@ impl Rule {
             "link" => Some(include_str!("../doc/yml/link.md").into()),
             "limit" => Some(include_str!("../doc/yml/limit.md").into()),
             "action" => Some(include_str!("../doc/yml/action.md").into()),
+            "name" | "params" | "suggest" | "replace" | "remove" | "edit" | "convert" => {
+                action_info(token).map(Cow::from)
+            }
             _ => None,
         }
     }
@@ -267,3 +846,197 @@ impl Rule {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        let src = "extends: substitution\nswap:\n  '(?!foo)bar': baz\n  \"a(b\": c\n  d[: e\ntokens:\n  - ok\n  - '(unclosed'\nfirst: '\\b(\\w+'\n";
+        let lines: Vec<u32> = diagnose(src).iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![3, 4, 7, 8]);
+
+        let d = &diagnose(src)[2];
+        assert_eq!(d.range.start.character, 4);
+        assert_eq!(d.range.end.character, 15);
+    }
+
+    #[test]
+    fn keys() {
+        let mut rule = Rule::invalid();
+        assert!(!rule.keys().contains(&"tokens"));
+
+        rule.extends = Extends::Substitution;
+        let keys = rule.keys();
+        assert!(keys.contains(&"swap"));
+        assert!(keys.contains(&"message"));
+        assert!(!keys.contains(&"tokens"));
+
+        assert!(!rule.complete("\n", None).unwrap().is_empty());
+        assert!(rule.complete("  - foo\n", None).unwrap().is_empty());
+
+        let items = rule.complete("ig", None).unwrap();
+        let item = items.iter().find(|i| i.label == "ignorecase").unwrap();
+        assert_eq!(item.insert_text.as_deref(), Some("ignorecase: "));
+        assert!(item.documentation.is_some());
+    }
+
+    #[test]
+    fn scopes() {
+        let rule = Rule::invalid();
+
+        let items = rule.complete("scope: ", None).unwrap();
+        assert!(items.iter().any(|i| i.label == "heading.h2"));
+        assert!(items.iter().any(|i| i.label == "table.cell"));
+        assert!(items.iter().all(|i| i.documentation.is_some()));
+        assert!(rule
+            .complete("level: ", None)
+            .unwrap()
+            .iter()
+            .all(|i| i.label != "raw"));
+    }
+
+    #[test]
+    fn actions() {
+        let src = "extends: existence\naction:\n  name: edit\n  params:\n    - \nlevel: error\n";
+        let rule = Rule::invalid();
+
+        assert_eq!(block(src, 0), None);
+        let (key, text) = block(src, 4).unwrap();
+        assert_eq!(key, "action");
+        assert_eq!(text, "action:\n  name: edit\n  params:\n    - ");
+
+        let names = rule.complete("  name: ", block(src, 2)).unwrap();
+        assert_eq!(names.len(), 5);
+        assert!(names.iter().all(|i| i.documentation.is_some()));
+
+        let params = rule.complete("    - ", block(src, 4)).unwrap();
+        assert!(params.iter().any(|i| i.label == "trim_right"));
+
+        let keys = rule.complete("  ", block(src, 2)).unwrap();
+        assert_eq!(keys.len(), 2);
+
+        let rule = Rule::new(".github/styles/Test/Rule.yml").unwrap();
+        assert!(rule.token_info("remove").is_some());
+    }
+
+    #[test]
+    fn skeletons() {
+        let items = templates(false);
+        assert_eq!(items.len(), 11);
+
+        let existence = items[0].insert_text.as_deref().unwrap();
+        assert!(existence.starts_with(
+            "extends: existence\nmessage: \"Consider removing '%s'.\"\nlevel: suggestion\n"
+        ));
+        assert!(!existence.contains("${"));
+        assert!(templates(true)[0]
+            .insert_text
+            .as_deref()
+            .unwrap()
+            .contains("${1:"));
+
+        for item in items {
+            let text = item.insert_text.unwrap();
+            assert!(YamlLoader::load_from_str(&text).is_ok(), "{}", text);
+        }
+    }
+
+    /// `expand` inserts a snippet the way a client would, taking the first
+    /// choice of each choice and the default of each placeholder.
+    fn expand(snippet: &str) -> String {
+        let mut out = String::new();
+        let mut chars = snippet.chars().peekable();
+        let mut depth = 0;
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if matches!(chars.peek(), Some('$' | '}' | '\\')) => {
+                    out.push(chars.next().unwrap());
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    while chars.peek().map_or(false, |c| c.is_ascii_digit()) {
+                        chars.next();
+                    }
+                    match chars.next() {
+                        Some(':') => depth += 1,
+                        Some('|') => {
+                            let choices: String =
+                                chars.by_ref().take_while(|c| *c != '}').collect();
+                            out.push_str(choices.split(&[',', '|'][..]).next().unwrap());
+                        }
+                        _ => {}
+                    }
+                }
+                '}' if depth > 0 => depth -= 1,
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn valid_skeletons() {
+        let plain = templates(false);
+        for (item, (name, body)) in templates(true).iter().zip(TEMPLATES) {
+            let expanded = expand(item.insert_text.as_deref().unwrap());
+            assert!(diagnose(&expanded).is_empty(), "{}: {}", name, expanded);
+
+            let stripped = plain.iter().find(|i| i.label == item.label).unwrap();
+            let stripped = stripped.insert_text.as_deref().unwrap();
+            assert!(diagnose(stripped).is_empty(), "{}: {}", name, stripped);
+            assert_eq!(stripped, expanded, "{}", body);
+        }
+
+        let conditional = expand(TEMPLATES[5].1);
+        assert!(conditional.contains("first: '\\b([A-Z]{3,5})\\b'\n"));
+    }
+
+    #[test]
+    fn examples() {
+        for (name, _) in TEMPLATES {
+            let yaml = example(name).unwrap();
+            assert!(yaml.contains(&format!("extends: {}", name)), "{}", yaml);
+            assert!(YamlLoader::load_from_str(yaml).is_ok(), "{}", yaml);
+        }
+        assert!(example("nope").is_none());
+    }
+
+    #[test]
+    fn folding() {
+        let src = "extends: substitution\nswap:\n  a: b\n  c: d\n\nexceptions:\n- e\n- f\nlevel: error\ntokens: [g]\n";
+        let ranges = folding_ranges(src);
+
+        assert_eq!(ranges.len(), 2);
+        assert_eq!((ranges[0].start_line, ranges[0].end_line), (1, 3));
+        assert_eq!((ranges[1].start_line, ranges[1].end_line), (5, 7));
+    }
+
+    #[test]
+    fn single_token() {
+        assert_eq!(selected_token("  - '\\bfoo\\b'", false), "\\bfoo\\b");
+        assert_eq!(
+            selected_token("[Oo]bservability", false),
+            "[Oo]bservability"
+        );
+        assert_eq!(selected_token("'a: b': c", true), "a: b");
+        assert_eq!(selected_token("utilize: use", true), "utilize");
+        assert_eq!(selected_token("utilize", true), "utilize");
+
+        let src = "extends: existence\nmessage: '%s'\ntokens:\n  - foo\n  - bar\n";
+        let rule = with_token(src, "bar").unwrap();
+        let parsed = &YamlLoader::load_from_str(&rule).unwrap()[0];
+        assert_eq!(parsed["tokens"].as_vec().unwrap().len(), 1);
+        assert_eq!(parsed["tokens"][0].as_str(), Some("bar"));
+        assert_eq!(parsed["message"].as_str(), Some("%s"));
+
+        let src = "extends: substitution\nswap:\n  utilize: use\n  leverage: use\n";
+        let rule = with_token(src, "leverage").unwrap();
+        let parsed = &YamlLoader::load_from_str(&rule).unwrap()[0];
+        assert_eq!(parsed["swap"].as_hash().unwrap().len(), 1);
+        assert_eq!(parsed["swap"]["leverage"].as_str(), Some("use"));
+
+        assert!(with_token("extends: metric\nformula: x\n", "x").is_none());
+    }
+}